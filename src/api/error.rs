@@ -0,0 +1,60 @@
+use crate::report::diagnostic::{Diagnostic, ErrorKind};
+
+use std::error;
+use std::fmt;
+
+//what can go wrong when the interpreter is driven from rust
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    //every lexical and syntax error of the source, nothing was run
+    Syntax(Vec<Diagnostic>),
+    //runtime or type error, with traceback
+    Runtime(Diagnostic),
+    //not a failure: script called exit(code)
+    Exit(u8)
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Syntax(errors) => errors.first()
+                .map(|error| error.kind)
+                .unwrap_or(ErrorKind::Syntax),
+            Error::Runtime(error) => error.kind,
+            Error::Exit(code) => ErrorKind::Exit(*code)
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self {
+            Error::Syntax(errors) => errors,
+            Error::Runtime(error) => std::slice::from_ref(error),
+            Error::Exit(_) => &[]
+        }
+    }
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Error {
+        match diagnostic.kind {
+            ErrorKind::Exit(code) => Error::Exit(code),
+            ErrorKind::Lexical | ErrorKind::Syntax => Error::Syntax(vec![diagnostic]),
+            _ => Error::Runtime(diagnostic)
+        }
+    }
+}
+
+//one diagnostic per line, use report::render for the full form
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Error::Exit(code) = self {
+            return write!(f, "exit with status {}", code);
+        }
+        let lines: Vec<String> = self.diagnostics().iter()
+            .map(|error| error.to_string())
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,104 @@
+use crate::parser::ast::Program;
+use crate::parser::builder::build;
+use crate::report::diagnostic::Diagnostic;
+use crate::runner::convert::{FromObject, IntoObject};
+use crate::runner::interpreter::Engine;
+use crate::runner::object::Object;
+
+use super::error::Error;
+use super::native::NativeFunction;
+
+use std::io::{BufRead, Write};
+
+//entry point for embedding the language:
+//state (globals, functions) lives as long as the interpreter
+pub struct Interpreter {
+    engine: Engine
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            engine: Engine::new()
+        }
+    }
+
+    //what the script gets from args()
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.engine.set_args(args);
+    }
+
+    //runs source as a script, only top level 'return' is echoed
+    pub fn run(&mut self, source: &str) -> Result<(), Error> {
+        let program = parse(source)?;
+        self.engine.run(&program).map_err(Error::from)
+    }
+
+    //gives value of the trailing expression statement like the repl does
+    pub fn eval(&mut self, source: &str) -> Result<Option<Object>, Error> {
+        let program = parse(source)?;
+        self.engine.eval(&program).map_err(Error::from)
+    }
+
+    //missing globals are null, so get::<Option<T>> tells if it is set
+    pub fn get<T: FromObject>(&self, name: &str) -> Result<T, Error> {
+        T::from_object(self.engine.memory().get_var(name)).map_err(Error::from)
+    }
+
+    pub fn set(&mut self, name: &str, value: impl IntoObject) {
+        self.engine.set_global(name, value.into_object());
+    }
+
+    //calls a script function (or builtin) stored in a global
+    pub fn call<T: FromObject>(&mut self, name: &str, args: Vec<Object>) -> Result<T, Error> {
+        let callee = match self.engine.memory().get_var(name) {
+            Object::Null => return Err(Error::Runtime(
+                Diagnostic::runtime(format!("No function named '{}'", name)))),
+            callee => callee
+        };
+        let result = self.engine.call(&callee, args)?;
+        T::from_object(result).map_err(Error::from)
+    }
+
+    //echo, print and input use stdio unless these are changed,
+    //runner::streams::Buffer collects the output in memory
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.engine.set_output(output);
+    }
+
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.engine.set_input(input);
+    }
+
+    //rust function with typed arguments and result, for example
+    //interpreter.register("add", |a: i64, b: i64| a + b);
+    pub fn register<Args>(&mut self, name: &str, function: impl NativeFunction<Args> + 'static) {
+        self.engine.define_native(name, move |_, args| function.call(args));
+    }
+
+    //rust function working with raw objects and the engine,
+    //it can call script functions through Engine::call
+    pub fn register_native(&mut self, name: &str,
+    function: impl Fn(&mut Engine, Vec<Object>) -> Result<Object, Diagnostic> + 'static) {
+        self.engine.define_native(name, function);
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+fn parse(source: &str) -> Result<Program, Error> {
+    let (program, errors) = build(source);
+    if errors.is_empty() { Ok(program) } else { Err(Error::Syntax(errors)) }
+}
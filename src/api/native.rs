@@ -0,0 +1,34 @@
+use crate::report::diagnostic::Diagnostic;
+use crate::runner::convert::{FromObject, IntoResult};
+use crate::runner::object::Object;
+
+//rust closures with typed arguments, Args is the tuple of argument types
+//missing arguments are null like in script functions, extra ones are ignored
+pub trait NativeFunction<Args> {
+    fn call(&self, args: Vec<Object>) -> Result<Object, Diagnostic>;
+}
+
+macro_rules! impl_native_function {
+    ($($arg:ident),*) => {
+        impl<Func, Ret, $($arg),*> NativeFunction<($($arg,)*)> for Func
+        where
+            Func: Fn($($arg),*) -> Ret,
+            Ret: IntoResult,
+            $($arg: FromObject),*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&self, args: Vec<Object>) -> Result<Object, Diagnostic> {
+                let mut args = args.into_iter();
+                $(let $arg = $arg::from_object(args.next().unwrap_or(Object::Null))?;)*
+                self($($arg),*).into_result()
+            }
+        }
+    };
+}
+
+impl_native_function!();
+impl_native_function!(A);
+impl_native_function!(A, B);
+impl_native_function!(A, B, C);
+impl_native_function!(A, B, C, D);
+impl_native_function!(A, B, C, D, E);
@@ -0,0 +1,96 @@
+pub const USAGE: &str = "\
+usage: language [command] [options] [file] [-- script arguments]
+
+commands:
+    run <file>      run the script (default when a file is given)
+    check <file>    only parse the script and report syntax errors
+    tokens <file>   print tokens of the script
+    ast <file>      print syntax tree of the script
+    test <file>     run every test_* function of the script
+    repl            start interactive session (default without a file)
+
+options:
+    -e <code>       use the code instead of a file
+    --time          print execution time after running
+    -h, --help      show this message
+
+everything after '--' is passed to the script";
+
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    File(String),
+    Inline(String)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(Source),
+    Check(Source),
+    Tokens(Source),
+    Ast(Source),
+    Test(Source),
+    Repl,
+    Help
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    //scripts read them through args()
+    pub script_args: Vec<String>,
+    pub show_time: bool
+}
+
+//args without the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.into_iter();
+    let mut command_name: Option<String> = None;
+    let mut source: Option<Source> = None;
+    let mut show_time = false;
+    let mut script_args: Vec<String> = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--" => {
+                script_args.extend(args.by_ref());
+            },
+            "-h" | "--help" => {
+                command_name = Some("help".to_string());
+            },
+            "--time" => show_time = true,
+            "-e" => {
+                let code = args.next().ok_or("Expected code after '-e'")?;
+                set_source(&mut source, Source::Inline(code))?;
+            },
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("Unknown option '{}'", arg));
+            },
+            "run" | "check" | "tokens" | "ast" | "test" | "repl"
+                if command_name.is_none() && source.is_none() => {
+                command_name = Some(arg);
+            },
+            _ => set_source(&mut source, Source::File(arg))?
+        }
+    }
+
+    let command = match (command_name.as_deref(), source) {
+        (Some("help"), _) => Command::Help,
+        (Some("repl"), None) | (None, None) => Command::Repl,
+        (Some("repl"), Some(_)) => return Err("Command 'repl' doesn't take a file".to_string()),
+        (Some(name), None) => return Err(format!("Command '{}' expects a file or '-e <code>'", name)),
+        (Some("check"), Some(source)) => Command::Check(source),
+        (Some("tokens"), Some(source)) => Command::Tokens(source),
+        (Some("ast"), Some(source)) => Command::Ast(source),
+        (Some("test"), Some(source)) => Command::Test(source),
+        (_, Some(source)) => Command::Run(source)
+    };
+    Ok(Options { command, script_args, show_time })
+}
+
+fn set_source(source: &mut Option<Source>, new: Source) -> Result<(), String> {
+    if source.is_some() {
+        return Err("Only one file or '-e <code>' can be given".to_string());
+    }
+    *source = Some(new);
+    Ok(())
+}
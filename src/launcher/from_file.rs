@@ -1,10 +1,12 @@
-use super::launcher::launch;
+use language::report::diagnostic::{Diagnostic, ErrorKind};
+use language::report::render::render;
 
-use std::env;
 use std::fs;
 
-pub fn launch_from_file(path: &str) {
-    let contents = fs::read_to_string(path)
-        .expect("Can't read file!");
-    launch(&contents);
-}
\ No newline at end of file
+pub fn read_source(path: &str) -> Result<String, ErrorKind> {
+    fs::read_to_string(path).map_err(|error| {
+        let diagnostic = Diagnostic::io(format!("Can't read file '{}': {}", path, error));
+        eprint!("{}", render(&diagnostic, "", path));
+        ErrorKind::Io
+    })
+}
@@ -1,29 +1,104 @@
-use crate::lexer::stream::Stream;
-use crate::parser::builder::Builder;
-use crate::runner::interpreter::Engine;
+use language::lexer::stream::Stream;
+use language::lexer::token::Token;
+use language::parser::ast::Program;
+use language::parser::builder::build;
+use language::report::diagnostic::{Diagnostic, ErrorKind};
+use language::report::render::render;
+use language::{Error, Interpreter};
 
 use std::time::Instant;
 
-pub fn launch(code: &str) {
-    let stream = Stream::new(code);
-    let mut builder = Builder::new(stream);
-    let ast = builder.build();
-    match ast {
-        Ok(program) => {
-            let start = Instant::now();
-            let mut engine = Engine::new();
-            match engine.run(&program) {
-                Ok(()) => {
-                    let time_wasted = start.elapsed().as_millis();
-                    println!("Finished with time: {}ms", time_wasted);
-                },
-                Err(exec_error) => {
-                    println!("From execution: {}", exec_error);
-                }
+//process exit codes
+pub const EXIT_RUNTIME: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_SYNTAX: i32 = 3;
+pub const EXIT_IO: i32 = 4;
+
+pub fn exit_code(kind: ErrorKind) -> i32 {
+    match kind {
+        ErrorKind::Lexical | ErrorKind::Syntax => EXIT_SYNTAX,
+        ErrorKind::Runtime | ErrorKind::Type => EXIT_RUNTIME,
+        ErrorKind::Io => EXIT_IO,
+        ErrorKind::Exit(code) => i32::from(code)
+    }
+}
+
+//name is used in error messages to point at the source
+//errors are printed here, the caller only learns their kind
+pub fn launch(code: &str, name: &str, args: &[String], show_time: bool) -> Result<(), ErrorKind> {
+    let start = Instant::now();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(args.to_vec());
+    match interpreter.run(code) {
+        Ok(()) => {
+            if show_time {
+                let time_wasted = start.elapsed().as_millis();
+                println!("Finished with time: {}ms", time_wasted);
             }
+            Ok(())
         },
-        Err(parser_error) => {
-            println!("From parser: {}", parser_error);
+        Err(error) => {
+            report_error(&error, code, name);
+            Err(error.kind())
+        }
+    }
+}
+
+pub fn check(code: &str, name: &str) -> Result<(), ErrorKind> {
+    parse(code, name).map(|_| ()).ok_or(ErrorKind::Syntax)
+}
+
+pub fn print_tokens(code: &str, name: &str) -> Result<(), ErrorKind> {
+    let mut result = Ok(());
+    for token in Stream::new(code) {
+        match token {
+            Ok(token) => {
+                println!("{} {:?}", token.span, token.node);
+                if token.node == Token::EOF {
+                    break;
+                }
+            },
+            Err(error) => {
+                report(&error, code, name);
+                result = Err(error.kind);
+            }
         }
     }
-}
\ No newline at end of file
+    result
+}
+
+pub fn print_ast(code: &str, name: &str) -> Result<(), ErrorKind> {
+    let program = parse(code, name).ok_or(ErrorKind::Syntax)?;
+    for statement in &program.0 {
+        println!("{:#?}", statement);
+    }
+    Ok(())
+}
+
+//prints all syntax errors, gives program only if there were none
+pub fn parse(code: &str, name: &str) -> Option<Program> {
+    let (program, errors) = build(code);
+    if errors.is_empty() {
+        return Some(program);
+    }
+    report_error(&Error::Syntax(errors), code, name);
+    None
+}
+
+pub fn report_error(error: &Error, code: &str, name: &str) {
+    let errors = error.diagnostics();
+    for error in errors {
+        report(error, code, name);
+    }
+    if errors.len() > 1 {
+        eprintln!("error: aborting due to {} previous errors", errors.len());
+    }
+}
+
+//exit() is not an error, so there is nothing to print
+pub fn report(error: &Diagnostic, code: &str, name: &str) {
+    if let ErrorKind::Exit(_) = error.kind {
+        return;
+    }
+    eprint!("{}", render(error, code, name));
+}
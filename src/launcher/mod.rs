@@ -1,3 +1,6 @@
+#[allow(clippy::module_inception)]
 pub mod launcher;
+pub mod cli;
 pub mod from_file;
-pub mod repl;
\ No newline at end of file
+pub mod repl;
+pub mod test_runner;
@@ -1,14 +1,186 @@
-use std::io::stdin;
-use super::launcher::launch;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
 
-pub fn enter_repl() {
+use super::launcher::{print_ast, print_tokens, report_error};
+use language::parser::builder::{build, is_incomplete};
+use language::report::diagnostic::ErrorKind;
+use language::{Error, Interpreter, Object};
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+const HELP: &str = "\
+:vars           show variables of every scope
+:ast <code>     show syntax tree of the code
+:tokens <code>  show tokens of the code
+:load <file>    run the file in this session
+:reset          forget everything defined so far
+:time           toggle printing of execution time
+:help           show this message
+exit            leave";
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".language_history"))
+}
+
+//one interpreter for the whole session, so variables and functions
+//survive between inputs
+struct Session {
+    interpreter: Interpreter,
+    show_time: bool
+}
+
+impl Session {
+    fn new() -> Session {
+        Session {
+            interpreter: Interpreter::new(),
+            show_time: false
+        }
+    }
+
+    //gives exit status if the code called exit()
+    fn eval(&mut self, code: &str, name: &str, echo: bool) -> Option<u8> {
+        let start = Instant::now();
+        match self.interpreter.eval(code) {
+            Ok(Some(object)) if echo => println!("{}", object),
+            Ok(_) => (),
+            Err(Error::Exit(code)) => return Some(code),
+            //timing of code which didn't parse means nothing
+            Err(error @ Error::Syntax(_)) => {
+                report_error(&error, code, name);
+                return None;
+            },
+            Err(error) => report_error(&error, code, name)
+        }
+        if self.show_time {
+            println!("Finished with time: {}ms", start.elapsed().as_millis());
+        }
+        None
+    }
+
+    //lines starting with ':'
+    fn command(&mut self, line: &str) -> Option<u8> {
+        let line = line.trim();
+        let (command, argument) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, "")
+        };
+        match command {
+            ":vars" => self.show_vars(),
+            ":ast" => show_ast(argument),
+            ":tokens" => {
+                let _ = print_tokens(argument, "<repl>");
+            },
+            ":load" => match fs::read_to_string(argument) {
+                Ok(code) => return self.eval(&code, argument, false),
+                Err(error) => eprintln!("Can't read file '{}': {}", argument, error)
+            },
+            ":reset" => self.interpreter = Interpreter::new(),
+            ":time" => {
+                self.show_time = !self.show_time;
+                println!("Timing is {}", if self.show_time {"on"} else {"off"});
+            },
+            ":help" => println!("{}", HELP),
+            _ => eprintln!("Unknown command '{}', try :help", command)
+        }
+        None
+    }
+
+    //builtins are skipped, they are always there
+    fn show_vars(&self) {
+        for (depth, scope) in self.interpreter.engine().memory().scopes().iter().enumerate() {
+            println!("scope {}:", depth);
+            for (name, value) in scope {
+                if let Object::BuiltIn(_) = value {
+                    continue;
+                }
+                println!("  {} = {}", name, value);
+            }
+        }
+    }
+}
+
+fn show_ast(code: &str) {
+    //lets ':ast 1 + 2' work without the semicolon
+    let mut code = code.to_string();
+    if !code.ends_with(';') && !code.ends_with('}') {
+        code.push(';');
+    }
+    let _ = print_ast(&code, "<repl>");
+}
+
+//Err only when the code called exit()
+pub fn enter_repl() -> Result<(), ErrorKind> {
+    let mut editor = DefaultEditor::new().expect("Can't start line editor!");
+    let history = history_path();
+    if let Some(path) = &history {
+        //there is no history on the first run
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = Session::new();
+    let mut status = None;
+    //lines of the statement being typed
     let mut input = String::new();
     loop {
-        stdin().read_line(&mut input).expect("Ooops!");
-        if input == "exit" {
+        let prompt = if input.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            //Ctrl-C drops the unfinished input, Ctrl-D leaves
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            },
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("Can't read input: {}", error);
+                break;
+            }
+        };
+        if input.is_empty() {
+            if line.trim() == "exit" {
+                break;
+            }
+            if line.trim_start().starts_with(':') {
+                let _ = editor.add_history_entry(line.trim());
+                status = session.command(&line);
+                if status.is_some() {
+                    break;
+                }
+                continue;
+            }
+        }
+        input.push_str(&line);
+        input.push('\n');
+        if input.trim().is_empty() {
+            input.clear();
+            continue;
+        }
+
+        let (_, errors) = build(&input);
+        if is_incomplete(&errors, &input) {
+            continue;
+        }
+        let _ = editor.add_history_entry(input.trim_end());
+        status = session.eval(&input, "<repl>", true);
+        if status.is_some() {
             break;
         }
-        launch(&input);
         input.clear();
     }
-}
\ No newline at end of file
+
+    if let Some(path) = &history {
+        if let Err(error) = editor.save_history(path) {
+            eprintln!("Can't save history: {}", error);
+        }
+    }
+    match status {
+        Some(code) => Err(ErrorKind::Exit(code)),
+        None => Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use language::parser::ast::StatementKind;
+use language::report::diagnostic::ErrorKind;
+use language::report::render::render;
+use language::{Buffer, Error, Interpreter, Object};
+
+use super::launcher::{parse, report_error};
+
+use std::io;
+
+const PREFIX: &str = "test_";
+
+//top level functions named test_*, in the order they are declared
+fn find_tests(code: &str, name: &str) -> Option<Vec<String>> {
+    let program = parse(code, name)?;
+    Some(program.0.iter()
+        .filter_map(|statement| match &statement.node {
+            StatementKind::FunctionDecl(function, _, _) if function.starts_with(PREFIX) =>
+                Some(function.clone()),
+            _ => None
+        })
+        .collect())
+}
+
+//top level code is checked (and its output shown) once before the tests
+fn set_up(code: &str, name: &str) -> Result<(), ErrorKind> {
+    match Interpreter::new().run(code) {
+        //'exit' only stops the top level code, the tests declared before it still run
+        Ok(()) | Err(Error::Exit(_)) => Ok(()),
+        Err(error) => {
+            report_error(&error, code, name);
+            Err(error.kind())
+        }
+    }
+}
+
+//every test gets its own interpreter, so tests can't see each other's globals;
+//the top level code runs again for it, with the output thrown away
+fn run_test(code: &str, test: &str) -> Result<(), Error> {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Buffer::new());
+    match interpreter.run(code) {
+        Ok(()) | Err(Error::Exit(_)) => (),
+        Err(error) => return Err(error)
+    }
+    interpreter.set_output(io::stdout());
+    interpreter.call::<Object>(test, Vec::new())?;
+    Ok(())
+}
+
+pub fn run_tests(code: &str, name: &str) -> Result<(), ErrorKind> {
+    let tests = find_tests(code, name).ok_or(ErrorKind::Syntax)?;
+    set_up(code, name)?;
+    println!("running {} test{}", tests.len(), if tests.len() == 1 { "" } else { "s" });
+
+    let mut failures: Vec<(&str, String)> = Vec::new();
+    for test in &tests {
+        match run_test(code, test) {
+            Ok(()) => println!("test {} ... ok", test),
+            Err(error) => {
+                println!("test {} ... FAILED", test);
+                let message = match &error {
+                    Error::Exit(_) => format!("{}\n", error),
+                    _ => error.diagnostics().iter()
+                        .map(|diagnostic| render(diagnostic, code, name))
+                        .collect()
+                };
+                failures.push((test, message));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (test, message) in &failures {
+            println!("\n---- {} ----\n{}", test, message.trim_end());
+        }
+    }
+    let passed = tests.len() - failures.len();
+    let status = if failures.is_empty() { "ok" } else { "FAILED" };
+    println!("\ntest result: {}. {} passed; {} failed", status, passed, failures.len());
+    if failures.is_empty() { Ok(()) } else { Err(ErrorKind::Runtime) }
+}
@@ -1,2 +1,3 @@
+pub mod span;
 pub mod stream;
-pub mod token;
\ No newline at end of file
+pub mod token;
@@ -0,0 +1,54 @@
+use std::fmt;
+
+//position of a piece of source code
+//start and end are byte offsets, line and column point to the start (1-based)
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span { start, end, line, column }
+    }
+
+    //span covering both self and other
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+//any value (token, ast node) together with its position
+#[derive(PartialEq, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span
+}
+
+//position is printed after the node to keep ast dumps readable
+impl<T: fmt::Debug> fmt::Debug for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.node.fmt(f)?;
+        write!(f, " @{}", self.span)
+    }
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Spanned<T> {
+        Spanned { node, span }
+    }
+}
@@ -2,11 +2,32 @@ use std::iter::Peekable;
 use std::str::Chars;
 use std::iter::Iterator;
 
+use crate::report::diagnostic::Diagnostic;
+
+use super::span::{Span, Spanned};
 use super::token::Token;
 
+#[derive(Clone)]
 pub struct Stream<'a> {
     chars: Peekable<Chars<'a>>,
-    current_char: Option<char>
+    current_char: Option<char>,
+    //position of the next char
+    offset: usize,
+    line: usize,
+    column: usize,
+    //strings whose '${...}' is being read, innermost last
+    templates: Vec<Template>,
+    //last token ends a value, so '.' after it can't start a number
+    after_value: bool
+}
+
+#[derive(Clone, Copy)]
+struct Template {
+    quote: char,
+    triple: bool,
+    raw: bool,
+    //'{' opened inside the interpolated expression
+    depth: usize
 }
 
 //added a small macro, for better flexibility
@@ -17,15 +38,30 @@ macro_rules! tok {
 }
 
 impl<'a> Stream<'a> {
-    pub fn new(source: &str) -> Stream {
+    pub fn new(source: &str) -> Stream<'_> {
         Stream {
             chars: source.chars().peekable(),
-            current_char: None
+            current_char: None,
+            offset: 0,
+            line: 1,
+            column: 1,
+            templates: Vec::new(),
+            after_value: false
         }
     }
 
     fn next(&mut self) -> Option<char> {
         self.current_char = self.chars.next();
+        if let Some(ch) = self.current_char {
+            self.offset += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            }
+            else {
+                self.column += 1;
+            }
+        }
         self.current_char
     }
 
@@ -33,6 +69,12 @@ impl<'a> Stream<'a> {
         self.chars.peek()
     }
 
+    fn peek_second(&self) -> Option<char> {
+        let mut chars = self.chars.clone();
+        chars.next();
+        chars.next()
+    }
+
     fn get_cur(&self) -> char {
         self.current_char.unwrap()
     }
@@ -53,20 +95,45 @@ impl<'a> Stream<'a> {
     }
 
     //core function
-    fn read_token(&mut self) -> Result<Token, String> {
-        self.eat_whitespace();
+    fn read_token(&mut self) -> Result<Spanned<Token>, Diagnostic> {
+        self.eat_trivia();
+        let (start, line, column) = (self.offset, self.line, self.column);
+        let token = self.read_token_kind();
+        self.after_value = matches!(token, Ok(Token::Ident(_))
+            | Ok(Token::Int(_)) | Ok(Token::Float(_)) | Ok(Token::Str(_)) | Ok(Token::TemplateTail(_))
+            | Ok(Token::True) | Ok(Token::False) | Ok(Token::Null)
+            | Ok(Token::RightBracket) | Ok(Token::RightSquare) | Ok(Token::RightBrace));
+        let span = Span::new(start, self.offset, line, column);
+        match token {
+            Ok(token) => Ok(Spanned::new(token, span)),
+            Err(message) => Err(Diagnostic::lexical(message, span))
+        }
+    }
 
+    fn read_token_kind(&mut self) -> Result<Token, String> {
         match self.next() {
             None => tok!(Token::EOF),
             Some(ch) => match ch {
                 '+' => tok!(Token::Add),
                 '-' => tok!(Token::Sub),
-                '*' => tok!(Token::Mul),
+                '*' => {
+                    if let Some('*') = self.peek() {
+                        self.next();
+                        tok!(Token::Pow)
+                    }
+                    else {
+                        tok!(Token::Mul)
+                    }
+                },
+                //'.5' is a number only where a value may start,
+                //after a value '.' is the string concatenation
+                '.' if !self.after_value && self.peek().copied().is_some_and(Stream::is_digit) =>
+                    self.read_number(),
                 '.' => tok!(Token::StrAdd),
                 '/' => {
                     if let Some('/') = self.peek() {
-                        self.eat_comment();
-                        self.read_token()
+                        self.next();
+                        tok!(Token::FloorDiv)
                     }
                     else {
                         tok!(Token::Div)
@@ -92,51 +159,72 @@ impl<'a> Stream<'a> {
                     }
                 },
                 '>' => {
-                    if let Some('=') = self.peek() {
-                        self.next();
-                        tok!(Token::GreaterOrEqual)
-                    }
-                    else {
-                        tok!(Token::Greater)
+                    match self.peek() {
+                        Some('=') => {
+                            self.next();
+                            tok!(Token::GreaterOrEqual)
+                        },
+                        Some('>') => {
+                            self.next();
+                            tok!(Token::ShiftRight)
+                        },
+                        _ => tok!(Token::Greater)
                     }
                 },
                 '<' => {
-                    if let Some('=') = self.peek() {
-                        self.next();
-                        tok!(Token::LessOrEqual)
-                    }
-                    else {
-                        tok!(Token::Less)
+                    match self.peek() {
+                        Some('=') => {
+                            self.next();
+                            tok!(Token::LessOrEqual)
+                        },
+                        Some('<') => {
+                            self.next();
+                            tok!(Token::ShiftLeft)
+                        },
+                        _ => tok!(Token::Less)
                     }
                 },
                 '&' => {
-                    if let Some('&') = self.next() {
+                    if let Some('&') = self.peek() {
+                        self.next();
                         tok!(Token::And)
                     }
                     else {
-                        Err(String::from("'&' isn't implemented yet!"))
+                        tok!(Token::BitAnd)
                     }
                 },
                 '|' => {
-                    if let Some('|') = self.next() {
+                    if let Some('|') = self.peek() {
+                        self.next();
                         tok!(Token::Or)
                     }
                     else {
-                        Err(String::from("'|' isn't implemented yet!"))
+                        tok!(Token::BitOr)
                     }
                 },
+                '^' => tok!(Token::BitXor),
+                '~' => tok!(Token::BitNot),
                 '(' => tok!(Token::LeftBracket),
                 ')' => tok!(Token::RightBracket),
-                '{' => tok!(Token::LeftBrace),
-                '}' => tok!(Token::RightBrace),
+                '{' => tok!(self.read_left_brace()),
+                '}' => self.read_right_brace(),
+                '[' => tok!(Token::LeftSquare),
+                ']' => tok!(Token::RightSquare),
                 ';' => tok!(Token::Semicolon),
                 ',' => tok!(Token::Comma),
+                ':' => tok!(Token::Colon),
+
+                //r"..." is a raw string
+                'r' if matches!(self.peek(), Some('"') | Some('\'')) => {
+                    self.next();
+                    self.read_string_literal(true)
+                },
 
                 'a' ..= 'z' | 'A' ..= 'Z' | '_' =>
                     tok!(self.read_word()),
                 
                 '"' | '\'' => 
-                    tok!(self.read_string_literal()),
+                    self.read_string_literal(false),
 
                 '0' ..= '9' => self.read_number(),
 
@@ -150,11 +238,21 @@ impl<'a> Stream<'a> {
     }
 
     //precore functions
+    fn eat_trivia(&mut self) {
+        loop {
+            self.eat_whitespace();
+            //'//' is floor division, so comments start with '#'
+            if let Some('#') = self.peek() {
+                self.eat_comment();
+            }
+            else {
+                break
+            }
+        }
+    }
+
     fn eat_whitespace(&mut self) {
-        self.read_while(|x| match x {
-            '\n' | '\t' | ' ' | '\r' => true,
-            _ => false
-        });
+        self.read_while(|x| matches!(x, '\n' | '\t' | ' ' | '\r'));
     }
 
     fn eat_comment(&mut self) {
@@ -173,6 +271,7 @@ impl<'a> Stream<'a> {
             "return" => Token::Return,
             "while" => Token::While,
             "function" => Token::Function,
+            "is" => Token::Is,
             "true" => Token::True,
             "false" => Token::False,
             "null" => Token::Null,
@@ -182,55 +281,250 @@ impl<'a> Stream<'a> {
         }
     }
 
-    //just a simple implementation. later fix that... Maybe not
-    fn read_string_literal(&mut self) -> Token {
-        let q = self.get_cur();
-        let value = self.read_while(|x| x != q);
+    //"..." and '...' end at the line, tripled quotes may span lines
+    //raw strings keep backslashes as they are and have no interpolation
+    fn read_string_literal(&mut self, raw: bool) -> Result<Token, String> {
+        let quote = self.get_cur();
+        let triple = self.peek() == Some(&quote) && self.peek_second() == Some(quote);
+        if triple {
+            self.next();
+            self.next();
+        }
+        self.read_string_part(Template { quote, triple, raw, depth: 0 }, false)
+    }
+
+    //reads string up to the closing quote or the next '${',
+    //continued is true for parts after an interpolated expression
+    fn read_string_part(&mut self, template: Template, continued: bool) -> Result<Token, String> {
+        let Template { quote, triple, raw, .. } = template;
+        let mut value = String::new();
+        //a bad escape is reported at the end of the part,
+        //so the rest of the string isn't read as code
+        let mut escape_error: Option<String> = None;
+        let interpolated = loop {
+            //newline is left out, so the error doesn't look like unfinished input
+            if !triple && self.peek() == Some(&'\n') {
+                return Err(String::from(
+                    "Unterminated string literal! Use triple quotes for multi-line strings"));
+            }
+            let ch = match self.next() {
+                Some(ch) => ch,
+                None => return Err(String::from("Unterminated string literal!"))
+            };
+            if ch == quote {
+                if !triple {
+                    break false;
+                }
+                if self.peek() == Some(&quote) && self.peek_second() == Some(quote) {
+                    self.next();
+                    self.next();
+                    break false;
+                }
+                value.push(ch);
+            }
+            else if ch == '$' && !raw && self.peek() == Some(&'{') {
+                self.next();
+                self.templates.push(template);
+                break true;
+            }
+            else if ch == '\\' && !raw {
+                match self.read_escape() {
+                    Ok(escaped) => value.push(escaped),
+                    Err(error) => {
+                        escape_error.get_or_insert(error);
+                    }
+                }
+            }
+            else {
+                value.push(ch);
+            }
+        };
+        if let Some(error) = escape_error {
+            return Err(error);
+        }
+        Ok(match (continued, interpolated) {
+            (false, false) => Token::Str(value),
+            (false, true) => Token::TemplateHead(value),
+            (true, true) => Token::TemplateMiddle(value),
+            (true, false) => Token::TemplateTail(value)
+        })
+    }
+
+    //'{' and '}' are counted inside '${...}',
+    //so only the matching '}' goes back to the string
+    fn read_left_brace(&mut self) -> Token {
+        if let Some(template) = self.templates.last_mut() {
+            template.depth += 1;
+        }
+        Token::LeftBrace
+    }
+
+    fn read_right_brace(&mut self) -> Result<Token, String> {
+        match self.templates.pop() {
+            Some(template) if template.depth == 0 => self.read_string_part(template, true),
+            Some(mut template) => {
+                template.depth -= 1;
+                self.templates.push(template);
+                tok!(Token::RightBrace)
+            },
+            None => tok!(Token::RightBrace)
+        }
+    }
+
+    //newline after '\\' is left for the string to fail on
+    fn read_escape(&mut self) -> Result<char, String> {
+        let ch = match self.peek() {
+            Some(&ch) if ch != '\n' => ch,
+            _ => return Err(String::from("Expected escape sequence after '\\'!"))
+        };
         self.next();
-        Token::Str(value)
+        match ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '$' => Ok('$'),
+            '\'' => Ok('\''),
+            'u' => self.read_unicode_escape(),
+            _ => Err(format!("Unknown escape sequence '\\{}'!", ch.escape_default()))
+        }
+    }
+
+    //\u{1F600}, from one to six hex digits
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        if self.peek() != Some(&'{') {
+            return Err(String::from("Expected '{' after '\\u'!"));
+        }
+        self.next();
+        let digits = self.read_while(|x| x.is_ascii_hexdigit());
+        if self.peek() != Some(&'}') {
+            return Err(String::from("Expected '}' to close unicode escape!"));
+        }
+        self.next();
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(format!("Invalid unicode escape '\\u{{{}}}'!", digits));
+        }
+        u32::from_str_radix(&digits, 16).ok()
+            .and_then(char::from_u32)
+            .ok_or(format!("'{}' is not a unicode code point!", digits))
     }
 
+    //12, 1_000, 1.5, .5, 1.5e-3, 0xFF, 0o17, 0b1010
+    //current char is the first digit or the leading '.'
     fn read_number(&mut self) -> Result<Token, String> {
+        let first = self.get_cur();
+        if first == '0' {
+            let radix = match self.peek() {
+                Some('x') => Some((16, "hexadecimal")),
+                Some('o') => Some((8, "octal")),
+                Some('b') => Some((2, "binary")),
+                _ => None
+            };
+            if let Some((radix, name)) = radix {
+                let prefix = self.next().unwrap();
+                return self.read_radix_number(radix, name, prefix);
+            }
+        }
+
         let mut number = String::new();
-        number.push(self.get_cur());
-        number.push_str(& self.read_while(Stream::is_digit));
-        if let Some('.') = self.peek() {
+        let mut is_float = false;
+        if first == '.' {
+            is_float = true;
+            number.push_str("0.");
+            number.push_str(&self.read_digits()?);
+        }
+        else {
+            number.push(first);
+            if self.peek() == Some(&'_') || self.peek().copied().is_some_and(Stream::is_digit) {
+                number.push_str(&self.read_digits()?);
+            }
+            if self.peek() == Some(&'.') && self.peek_second().is_some_and(Stream::is_digit) {
+                self.next();
+                is_float = true;
+                number.push('.');
+                number.push_str(&self.read_digits()?);
+            }
+        }
+        if let Some('e') | Some('E') = self.peek() {
             self.next();
-            number.push(self.get_cur());
-            number.push_str(& self.read_while(Stream::is_digit));
-            let val = number.parse::<f64>();
-            match val {
-                Ok(v) => Ok(Token::Float(v)),
+            is_float = true;
+            number.push('e');
+            if let Some(sign) = self.peek().copied().filter(|x| matches!(x, '+' | '-')) {
+                self.next();
+                number.push(sign);
+            }
+            if !self.peek().copied().is_some_and(Stream::is_digit) {
+                return Err(String::from("Expected digits in the exponent of the number!"));
+            }
+            number.push_str(&self.read_digits()?);
+        }
+        self.check_number_end()?;
+
+        if is_float {
+            match number.parse::<f64>() {
+                Ok(v) if v.is_finite() => Ok(Token::Float(v)),
+                Ok(_) => Err(format!("Float {} is too large!", number)),
                 Err(_) => Err(String::from("Can't parse floating number!"))
             }
         }
         else {
-            let val = number.parse::<i32>();
-            match val {
+            match number.parse::<i64>() {
                 Ok(v) => Ok(Token::Int(v)),
-                Err(_) => Err(String::from("Can't parse integer number!"))
+                Err(_) => Err(format!("Integer {} doesn't fit in 64 bits!", number))
             }
         }
     }
 
-    fn is_letter(x: char) -> bool {
-        match x {
-            '_' | 'a' ..= 'z' | 'A' ..= 'Z' | '0' ..= '9' => true,
-            _ => false
+    //decimal digits with '_' separators, which are dropped
+    fn read_digits(&mut self) -> Result<String, String> {
+        let digits = self.read_while(|x| Stream::is_digit(x) || x == '_');
+        if digits.ends_with('_') {
+            return Err(String::from("Digit separator '_' must be followed by a digit!"));
         }
+        Ok(digits.replace('_', ""))
     }
 
-    fn is_digit(x: char) -> bool {
-        match x {
-            '0' ..= '9' => true,
-            _ => false
+    //after 0x, 0o or 0b
+    fn read_radix_number(&mut self, radix: u32, name: &str, prefix: char) -> Result<Token, String> {
+        let digits = self.read_while(Stream::is_letter);
+        if !digits.chars().any(|x| x != '_') {
+            return Err(format!("Expected {} digits after '0{}'!", name, prefix));
         }
+        if let Some(bad) = digits.chars().find(|x| *x != '_' && !x.is_digit(radix)) {
+            return Err(format!("Invalid digit '{}' in {} number!", bad, name));
+        }
+        if digits.ends_with('_') {
+            return Err(String::from("Digit separator '_' must be followed by a digit!"));
+        }
+        match i64::from_str_radix(&digits.replace('_', ""), radix) {
+            Ok(v) => Ok(Token::Int(v)),
+            Err(_) => Err(format!("Integer 0{}{} doesn't fit in 64 bits!", prefix, digits))
+        }
+    }
+
+    //'12abc' is an error, not a number followed by a name
+    fn check_number_end(&mut self) -> Result<(), String> {
+        if self.peek().copied().is_some_and(Stream::is_letter) {
+            let suffix = self.read_while(Stream::is_letter);
+            return Err(format!("Invalid suffix '{}' after number!", suffix));
+        }
+        Ok(())
+    }
+
+    fn is_letter(x: char) -> bool {
+        matches!(x, '_' | 'a' ..= 'z' | 'A' ..= 'Z' | '0' ..= '9')
+    }
+
+    fn is_digit(x: char) -> bool {
+        x.is_ascii_digit()
     }
 
 }
 
 impl Iterator for Stream<'_> {
-    type Item = Result<Token, String>;
+    type Item = Result<Spanned<Token>, Diagnostic>;
     fn next(&mut self) -> Option<Self::Item> {
         Some(self.read_token())
     }
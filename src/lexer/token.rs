@@ -1,11 +1,16 @@
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     EOF,
     Ident(String),
-    Int(i32),
+    Int(i64),
     Float(f64),
     Str(String),
+    //"a${x}b${y}c" is TemplateHead("a"), x, TemplateMiddle("b"), y, TemplateTail("c")
+    TemplateHead(String),
+    TemplateMiddle(String),
+    TemplateTail(String),
 
     True,
     False,
@@ -19,6 +24,7 @@ pub enum Token {
     If,
     Else,
     Function,
+    Is,
 
     //вывод
     Echo,
@@ -29,6 +35,15 @@ pub enum Token {
     Mul,            //*
     Div,            // /
     Mod,            // %
+    FloorDiv,       // //
+    Pow,            // **
+
+    BitAnd,         // &
+    BitOr,          // |
+    BitXor,         // ^
+    BitNot,         // ~
+    ShiftLeft,      // <<
+    ShiftRight,     // >>
 
     Equal,          // ==
     NotEqual,       // !=
@@ -51,34 +66,32 @@ pub enum Token {
     RightBracket,   // )
     LeftBrace,      // {
     RightBrace,     // }
+    LeftSquare,     // [
+    RightSquare,    // ]
     Semicolon,      // ;
     Comma,          // ,
+    Colon,          // :
 }
 
 
 impl Token {
     pub fn is_ident(&self) -> bool {
-        if let Token::Ident(_) = self {true}
-        else {false}
+        matches!(self, Token::Ident(_))
     }
 
     pub fn is_int(&self) -> bool {
-        if let Token::Int(_) = self {true}
-        else {false}
+        matches!(self, Token::Int(_))
     }
 
     pub fn is_float(&self) -> bool {
-        if let Token::Float(_) = self {true}
-        else {false}
+        matches!(self, Token::Float(_))
     }
 
     pub fn is_str(&self) -> bool {
-        if let Token::Str(_) = self {true}
-        else {false}
+        matches!(self, Token::Str(_))
     }
 
     pub fn is_eof(&self) -> bool {
-        if let Token::EOF = self {true}
-        else {false}
+        matches!(self, Token::EOF)
     }
 }
@@ -0,0 +1,11 @@
+pub mod lexer;
+pub mod parser;
+pub mod runner;
+pub mod report;
+pub mod api;
+
+pub use api::error::Error;
+pub use api::interpreter::Interpreter;
+pub use runner::convert::{FromObject, IntoObject};
+pub use runner::object::Object;
+pub use runner::streams::Buffer;
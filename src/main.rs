@@ -1,17 +1,51 @@
-mod lexer;
-mod parser;
-mod runner;
 mod launcher;
 
-use launcher::from_file::launch_from_file;
+use launcher::cli::{parse_args, Command, Source, USAGE};
+use launcher::from_file::read_source;
+use launcher::launcher::{check, exit_code, launch, print_ast, print_tokens, EXIT_USAGE};
 use launcher::repl::enter_repl;
+use launcher::test_runner::run_tests;
+use language::report::diagnostic::ErrorKind;
 
 use std::env;
+use std::process;
+
+//gives code and the name to show in error messages
+fn load(source: &Source) -> Result<(String, String), ErrorKind> {
+    match source {
+        Source::File(path) => Ok((read_source(path)?, path.clone())),
+        Source::Inline(code) => Ok((code.clone(), "<inline>".to_string()))
+    }
+}
 
 fn main() {
-    let file_path = env::args().nth(1);
-    match file_path {
-        None => enter_repl(),
-        Some(path) => launch_from_file(&path)
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+
+    let result = match &options.command {
+        Command::Repl => enter_repl(),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        Command::Run(source) => load(source)
+            .and_then(|(code, name)| launch(&code, &name, &options.script_args, options.show_time)),
+        Command::Check(source) => load(source)
+            .and_then(|(code, name)| check(&code, &name)),
+        Command::Tokens(source) => load(source)
+            .and_then(|(code, name)| print_tokens(&code, &name)),
+        Command::Ast(source) => load(source)
+            .and_then(|(code, name)| print_ast(&code, &name)),
+        Command::Test(source) => load(source)
+            .and_then(|(code, name)| run_tests(&code, &name))
+    };
+
+    if let Err(kind) = result {
+        process::exit(exit_code(kind));
     }
-}
\ No newline at end of file
+}
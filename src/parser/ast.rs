@@ -1,9 +1,15 @@
+use crate::lexer::span::Spanned;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Program(pub Vec<Statement>);
 
+//every node knows where it came from
+pub type Statement = Spanned<StatementKind>;
+pub type Expression = Spanned<ExpressionKind>;
+pub type PrimaryExpression = Spanned<PrimaryExpressionKind>;
+
 #[derive(Debug, PartialEq, Clone)]
-pub enum Statement {
+pub enum StatementKind {
     List(Vec<Statement>),
     Break,
     Continue,
@@ -16,7 +22,7 @@ pub enum Statement {
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum Expression {
+pub enum ExpressionKind {
     Primary(PrimaryExpression),
     BinaryOperation(BinaryOperator, Box<Expression>, Box<Expression>)
 }
@@ -29,9 +35,18 @@ pub enum BinaryOperator {
     Mul,            //*
     Div,            // /
     Mod,            // %
+    FloorDiv,       // //
+    Pow,            // **
+
+    BitAnd,         // &
+    BitOr,          // |
+    BitXor,         // ^
+    ShiftLeft,      // <<
+    ShiftRight,     // >>
 
     Equal,          // ==
     NotEqual,       // !=
+    Is,             // is
     Greater,        // >
     Less,           // <
     GreaterOrEqual, // >=
@@ -46,17 +61,29 @@ pub enum BinaryOperator {
     StrAdd,
 }
 
+//pieces of "text ${expression} text", empty text is left out
 #[derive(Debug, PartialEq, Clone)]
-pub enum PrimaryExpression {
+pub enum TemplatePart {
+    Str(String),
+    Expr(Expression)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PrimaryExpressionKind {
     UnaryPlus(Box<PrimaryExpression>),
     UnaryMinus(Box<PrimaryExpression>),
     UnaryNot(Box<PrimaryExpression>),
+    UnaryBitNot(Box<PrimaryExpression>),
     InBrackets(Box<Expression>),
     Ident(String),
     Float(f64),
-    Int(i32),
+    Int(i64),
     Str(String),
+    Template(Vec<TemplatePart>),
     Boolean(bool),
     Call(Box<PrimaryExpression>, Vec<Expression>),
+    Array(Vec<Expression>),
+    Map(Vec<(Expression, Expression)>),
+    Index(Box<PrimaryExpression>, Box<Expression>),
     Null
-}
\ No newline at end of file
+}
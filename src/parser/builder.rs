@@ -1,6 +1,9 @@
+use crate::lexer::span::{Span, Spanned};
 use crate::lexer::stream::Stream;
 use crate::lexer::token::Token;
 
+use crate::report::diagnostic::Diagnostic;
+
 use super::ast::*;
 
 use std::iter::Peekable;
@@ -12,8 +15,16 @@ fn tr_op(token: Token) -> BinaryOperator {
         Token::Mul => BinaryOperator::Mul,
         Token::Div => BinaryOperator::Div,
         Token::Mod => BinaryOperator::Mod,
+        Token::FloorDiv => BinaryOperator::FloorDiv,
+        Token::Pow => BinaryOperator::Pow,
+        Token::BitAnd => BinaryOperator::BitAnd,
+        Token::BitOr => BinaryOperator::BitOr,
+        Token::BitXor => BinaryOperator::BitXor,
+        Token::ShiftLeft => BinaryOperator::ShiftLeft,
+        Token::ShiftRight => BinaryOperator::ShiftRight,
         Token::Equal => BinaryOperator::Equal,
         Token::NotEqual => BinaryOperator::NotEqual,
+        Token::Is => BinaryOperator::Is,
         Token::Greater => BinaryOperator::Greater,
         Token::Less => BinaryOperator::Less,
         Token::GreaterOrEqual => BinaryOperator::GreaterOrEqual,
@@ -27,32 +38,127 @@ fn tr_op(token: Token) -> BinaryOperator {
     }
 }
 
+fn bin_op(op: Token, left: Expression, right: Expression) -> Expression {
+    let span = left.span.to(&right.span);
+    Spanned::new(
+        ExpressionKind::BinaryOperation(
+            tr_op(op),
+            Box::new(left),
+            Box::new(right)
+        ),
+        span
+    )
+}
+
+//tokens parse_primary accepts, anything else is left in the stream
+//so that recovery can see the ';' ending the broken statement
+fn starts_primary(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Ident(_)
+            | Token::Int(_)
+            | Token::Float(_)
+            | Token::Str(_)
+            | Token::TemplateHead(_)
+            | Token::True
+            | Token::False
+            | Token::Null
+            | Token::LeftBracket
+            | Token::LeftSquare
+            | Token::LeftBrace
+            | Token::Add
+            | Token::Sub
+            | Token::Not
+            | Token::BitNot
+    )
+}
+
+//source which stops in the middle of a statement (open '{', '(' or string)
+//fails only at its very end, so more input could fix it
+pub fn is_incomplete(errors: &[Diagnostic], source: &str) -> bool {
+    !errors.is_empty() && errors.iter().all(|error| match error.span {
+        Some(span) => span.end >= source.len(),
+        None => false
+    })
+}
+
+//lexes and parses the whole source, the program is partial if there are errors
+pub fn build(code: &str) -> (Program, Vec<Diagnostic>) {
+    Builder::new(Stream::new(code)).build()
+}
+
 pub struct Builder<'a> {
-    stream: Peekable<Stream<'a>>
+    stream: Peekable<Stream<'a>>,
+    //span of the last consumed token
+    last_span: Span,
+    //number of consumed tokens, used to make sure recovery moves forward
+    position: usize,
+    errors: Vec<Diagnostic>
 }
 
 impl<'a> Builder<'a> {
-    pub fn new(stream: Stream) -> Builder {
+    pub fn new(stream: Stream<'a>) -> Builder<'a> {
         Builder {
-            stream: stream.peekable()
+            stream: stream.peekable(),
+            last_span: Span::default(),
+            position: 0,
+            errors: Vec::new()
         }
     }
 
     //core functions
 
-    fn next(&mut self) -> Result<Token, String> {
-        self.stream.next().unwrap()
+    fn next(&mut self) -> Result<Token, Diagnostic> {
+        self.position += 1;
+        let token = self.stream.next().unwrap()?;
+        self.last_span = token.span;
+        Ok(token.node)
     }
 
-    fn peek(&mut self) -> Result<Token, String> {
-        self.stream.peek().unwrap().clone()
+    fn peek(&mut self) -> Result<Token, Diagnostic> {
+        match self.stream.peek().unwrap() {
+            Ok(token) => Ok(token.node.clone()),
+            Err(message) => Err(message.clone())
+        }
     }
 
-    fn eat(&mut self, token_type: Token) -> Result<(), String> {
+    fn peek_span(&mut self) -> Result<Span, Diagnostic> {
+        match self.stream.peek().unwrap() {
+            Ok(token) => Ok(token.span),
+            Err(message) => Err(message.clone())
+        }
+    }
+
+    //looks n tokens past the next one, without consuming anything
+    fn peek_nth(&mut self, n: usize) -> Option<Token> {
+        let mut stream = self.stream.clone();
+        match stream.nth(n) {
+            Some(Ok(token)) => Some(token.node),
+            _ => None
+        }
+    }
+
+    //'{' at the start of a statement opens a block,
+    //unless it looks like '{ "key": ...'
+    fn is_map_literal(&mut self) -> bool {
+        matches!(
+            (self.peek_nth(1), self.peek_nth(2)),
+            (Some(Token::Str(_)), Some(Token::Colon))
+        )
+    }
+
+    //wraps node with span from start to the last consumed token
+    fn spanned<T>(&self, node: T, start: Span) -> Spanned<T> {
+        Spanned::new(node, start.to(&self.last_span))
+    }
+
+    fn eat(&mut self, token_type: Token) -> Result<(), Diagnostic> {
         let next_token = self.peek()?;
         if token_type != next_token {
-            Err(format!("Expected token of type '{:?}', not '{:?}'", 
-            token_type, next_token))
+            Err(Diagnostic::syntax(
+                format!("Expected token of type '{:?}', not '{:?}'", token_type, next_token),
+                self.peek_span()?
+            ))
         }
         else {
             self.next()?;
@@ -60,24 +166,81 @@ impl<'a> Builder<'a> {
         }
     }
 
+    //error recovery
+
+    //the same lexer error can come both from a failed statement
+    //and from skipping tokens, so it is reported once
+    fn report(&mut self, error: Diagnostic) {
+        if self.errors.last() != Some(&error) {
+            self.errors.push(error);
+        }
+    }
+
+    //panic mode: skips tokens until the end of the broken statement
+    //(';' is eaten, '}' and statement keywords are left for the caller)
+    fn synchronize(&mut self) {
+        loop {
+            match self.peek() {
+                Ok(Token::EOF)
+                | Ok(Token::RightBrace)
+                | Ok(Token::Break)
+                | Ok(Token::Continue)
+                | Ok(Token::Return)
+                | Ok(Token::Echo)
+                | Ok(Token::While)
+                | Ok(Token::If)
+                | Ok(Token::Function) => return,
+                Ok(Token::Semicolon) => {
+                    self.position += 1;
+                    self.stream.next();
+                    return
+                },
+                _ => self.skip()
+            }
+        }
+    }
+
+    fn skip(&mut self) {
+        self.position += 1;
+        if let Some(Err(error)) = self.stream.next() {
+            self.report(error);
+        }
+    }
+
+    //parses a statement, on error remembers it and recovers
+    fn parse_recovering(&mut self, statements: &mut Vec<Statement>) {
+        let position = self.position;
+        match self.parse_statement() {
+            Ok(statement) => statements.push(statement),
+            Err(error) => {
+                self.report(error);
+                self.synchronize();
+                if self.position == position {
+                    self.skip();
+                }
+            }
+        }
+    }
+
     //parsing functions
 
-    pub fn build(&mut self) -> Result<Program, String> {
-        let statements = self.parse_program()?;
-        Ok(Program(statements))
+    //returns everything that could be parsed and all errors found on the way
+    pub fn build(&mut self) -> (Program, Vec<Diagnostic>) {
+        let statements = self.parse_program();
+        (Program(statements), std::mem::take(&mut self.errors))
     }
 
-    fn parse_program(&mut self) -> Result<Vec<Statement>, String> {
+    fn parse_program(&mut self) -> Vec<Statement> {
         let mut statements: Vec<Statement> = Vec::new();
-        while !self.peek()?.is_eof() {
-            let statement = self.parse_statement()?;
-            statements.push(statement)
+        while !self.peek().is_ok_and(|token| token.is_eof()) {
+            self.parse_recovering(&mut statements);
         }
-        Ok(statements)
+        statements
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, String> {
+    fn parse_statement(&mut self) -> Result<Statement, Diagnostic> {
         match self.peek()? {
+            Token::LeftBrace if self.is_map_literal() => self.parse_expression_stmt(),
             Token::LeftBrace => self.parse_statement_list(),
             Token::Break => self.parse_break(),
             Token::Continue => self.parse_continue(),
@@ -91,62 +254,72 @@ impl<'a> Builder<'a> {
         }
     }
 
-    fn parse_statement_list(&mut self) -> Result<Statement, String> {
+    fn parse_statement_list(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::LeftBrace)?;
         let mut statements: Vec<Statement> = Vec::new();
         loop {
-            match self.peek()? {
-                Token::RightBrace | Token::EOF => {
+            match self.peek() {
+                Ok(Token::RightBrace) => {
                     self.next()?;
                     break;
                 },
-                _ => {
-                    let statement = self.parse_statement()?;
-                    statements.push(statement)
-                }
+                Ok(Token::EOF) => {
+                    let eof = self.peek_span()?;
+                    self.report(Diagnostic::syntax("Expected '}', found end of file", eof)
+                        .with_note(format!("block was opened at {}", start)));
+                    break;
+                },
+                _ => self.parse_recovering(&mut statements)
             }
         }
-        Ok(Statement::List(statements))
+        Ok(self.spanned(StatementKind::List(statements), start))
     }
 
-    fn parse_break(&mut self) -> Result<Statement, String> {
+    fn parse_break(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::Break)?;
         self.eat(Token::Semicolon)?;
-        Ok(Statement::Break)
+        Ok(self.spanned(StatementKind::Break, start))
     }
 
-    fn parse_continue(&mut self) -> Result<Statement, String> {
+    fn parse_continue(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::Continue)?;
         self.eat(Token::Semicolon)?;
-        Ok(Statement::Continue)
+        Ok(self.spanned(StatementKind::Continue, start))
     }
 
-    fn parse_return(&mut self) -> Result<Statement, String> {
+    fn parse_return(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::Return)?;
         let expression = self.parse_expression()?;
         self.eat(Token::Semicolon)?;
-        Ok(Statement::Return(expression))
+        Ok(self.spanned(StatementKind::Return(expression), start))
     }
 
     //вывод
-    fn parse_echo(&mut self) -> Result<Statement, String> {
+    fn parse_echo(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::Echo)?;
         let expression = self.parse_expression()?;
         self.eat(Token::Semicolon)?;
-        Ok(Statement::Echo(expression))
+        Ok(self.spanned(StatementKind::Echo(expression), start))
     }
 
-    fn parse_while(&mut self) -> Result<Statement, String> {
+    fn parse_while(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::While)?;
         self.eat(Token::LeftBracket)?;
         let expression = self.parse_expression()?;
         self.eat(Token::RightBracket)?;
         let statement = self.parse_statement()?;
-        Ok(Statement::While(expression, Box::new(statement)))
+        Ok(self.spanned(StatementKind::While(expression, Box::new(statement)), start))
 
     }
 
-    fn parse_if(&mut self) -> Result<Statement, String> {
+    fn parse_if(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::If)?;
         self.eat(Token::LeftBracket)?;
         let expression = self.parse_expression()?;
@@ -156,23 +329,24 @@ impl<'a> Builder<'a> {
             Token::Else => {
                 self.eat(Token::Else)?;
                 let statement2 = self.parse_statement()?;
-                Ok(Statement::If(
+                Ok(self.spanned(StatementKind::If(
                     expression,
                     Box::new(statement1),
                     Some(Box::new(statement2))
-                ))
+                ), start))
             },
             _ => {
-                Ok(Statement::If(
+                Ok(self.spanned(StatementKind::If(
                     expression, 
                     Box::new(statement1),
                     None 
-                ))
+                ), start))
             }
         }
     }
 
-    fn parse_function(&mut self) -> Result<Statement, String> {
+    fn parse_function(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         self.eat(Token::Function)?;
         let ident = self.next()?;
         match ident {
@@ -181,23 +355,29 @@ impl<'a> Builder<'a> {
                 let args = self.parse_decl_args()?;
                 self.eat(Token::RightBracket)?;
                 let body = self.parse_statement()?;
-                Ok(Statement::FunctionDecl(name, args, Box::new(body)))
+                Ok(self.spanned(
+                    StatementKind::FunctionDecl(name, args, Box::new(body)),
+                    start
+                ))
             },
-            _ => Err(format!(
-                "Expected function name, not '{:?}'", ident
+            _ => Err(Diagnostic::syntax(
+                format!("Expected function name, not '{:?}'", ident),
+                self.last_span
             ))
         }
     }
 
-    fn parse_decl_args(&mut self) -> Result<Vec<String>, String> {
+    fn parse_decl_args(&mut self) -> Result<Vec<String>, Diagnostic> {
         let mut args: Vec<String> = Vec::new();
         while self.peek()? != Token::RightBracket {
             if let Token::Ident(name) = self.next()? {
                 args.push(name);
             }
             else {
-                return Err("Expected idents as param name while function declaration"
-                .to_string())
+                return Err(Diagnostic::syntax(
+                    "Expected idents as param name while function declaration",
+                    self.last_span
+                ))
             }
             match self.peek()? {
                 Token::Comma => { self.next()?; },
@@ -207,9 +387,14 @@ impl<'a> Builder<'a> {
         Ok(args)
     }
 
-    fn parse_args(&mut self) -> Result<Vec<Expression>, String> {
+    fn parse_args(&mut self) -> Result<Vec<Expression>, Diagnostic> {
+        self.parse_expression_list(Token::RightBracket)
+    }
+
+    //comma separated expressions up to the closing token (not eaten)
+    fn parse_expression_list(&mut self, end: Token) -> Result<Vec<Expression>, Diagnostic> {
         let mut args: Vec<Expression> = Vec::new();
-        while self.peek()? != Token::RightBracket {
+        while self.peek()? != end {
             let expr = self.parse_expression()?;
             args.push(expr);
             match self.peek()? {
@@ -220,192 +405,287 @@ impl<'a> Builder<'a> {
         Ok(args)
     }
 
-    fn parse_expression_stmt(&mut self) -> Result<Statement, String> {
+    //'key: value' pairs up to '}' (not eaten)
+    fn parse_map_entries(&mut self) -> Result<Vec<(Expression, Expression)>, Diagnostic> {
+        let mut entries: Vec<(Expression, Expression)> = Vec::new();
+        while self.peek()? != Token::RightBrace {
+            let key = self.parse_expression()?;
+            self.eat(Token::Colon)?;
+            let value = self.parse_expression()?;
+            entries.push((key, value));
+            match self.peek()? {
+                Token::Comma => {self.next()?; },
+                _ => break
+            }
+        }
+        Ok(entries)
+    }
+
+    fn parse_expression_stmt(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span()?;
         let expression = self.parse_expression()?;
         self.eat(Token::Semicolon)?;
-        Ok(Statement::ExpressionStmt(expression))
+        Ok(self.spanned(StatementKind::ExpressionStmt(expression), start))
     }
 
     //expressions
-    fn parse_expression(&mut self) -> Result<Expression, String> {
+    fn parse_expression(&mut self) -> Result<Expression, Diagnostic> {
         self.expr1()
     }
 
-    fn expr1(&mut self) -> Result<Expression, String> {
+    fn expr1(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr2()?;
         while let Token::Assign = self.peek()? {
             let op = self.next()?;
             let right = self.expr2()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr2(&mut self) -> Result<Expression, String> {
+    fn expr2(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr3()?;
         while let Token::Or = self.peek()? {
             let op = self.next()?;
             let right = self.expr3()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr3(&mut self) -> Result<Expression, String> {
+    fn expr3(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr4()?;
         while let Token::And = self.peek()? {
             let op = self.next()?;
             let right = self.expr4()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr4(&mut self) -> Result<Expression, String> {
+    fn expr4(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr5()?;
-        while match self.peek()? {
-            Token::Equal | Token::NotEqual => true,
-            _ => false
-        }{
+        while matches!(self.peek()?, Token::Equal | Token::NotEqual | Token::Is) {
             let op = self.next()?;
             let right = self.expr5()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr5(&mut self) -> Result<Expression, String> {
+    fn expr5(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr6()?;
-        while match self.peek()? {
+        while matches!(self.peek()?,
             Token::Less 
             | Token::LessOrEqual
             | Token::Greater
-            | Token::GreaterOrEqual => true,
-            _ => false
-        }{
+            | Token::GreaterOrEqual
+        ){
             let op = self.next()?;
             let right = self.expr6()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr6(&mut self) -> Result<Expression, String> {
+    //bitwise operators bind tighter than comparisons,
+    //so 'x & 1 == 0' means '(x & 1) == 0'
+    fn expr6(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr7()?;
-        while match self.peek()? {
-            Token::Add | Token::Sub | Token::StrAdd => true,
-            _ => false
-        }{
+        while let Token::BitOr = self.peek()? {
             let op = self.next()?;
             let right = self.expr7()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr7(&mut self) -> Result<Expression, String> {
+    fn expr7(&mut self) -> Result<Expression, Diagnostic> {
         let mut left = self.expr8()?;
-        while match self.peek()? {
+        while let Token::BitXor = self.peek()? {
+            let op = self.next()?;
+            let right = self.expr8()?;
+            left = bin_op(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn expr8(&mut self) -> Result<Expression, Diagnostic> {
+        let mut left = self.expr9()?;
+        while let Token::BitAnd = self.peek()? {
+            let op = self.next()?;
+            let right = self.expr9()?;
+            left = bin_op(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn expr9(&mut self) -> Result<Expression, Diagnostic> {
+        let mut left = self.expr10()?;
+        while matches!(self.peek()?, Token::ShiftLeft | Token::ShiftRight) {
+            let op = self.next()?;
+            let right = self.expr10()?;
+            left = bin_op(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn expr10(&mut self) -> Result<Expression, Diagnostic> {
+        let mut left = self.expr11()?;
+        while matches!(self.peek()?, Token::Add | Token::Sub | Token::StrAdd) {
+            let op = self.next()?;
+            let right = self.expr11()?;
+            left = bin_op(op, left, right);
+        }
+        Ok(left)
+    }
+
+    fn expr11(&mut self) -> Result<Expression, Diagnostic> {
+        let mut left = self.expr12()?;
+        while matches!(self.peek()?,
             Token::Mul 
             | Token::Div
-            | Token::Mod => true,
-            _ => false
-        }{
+            | Token::FloorDiv
+            | Token::Mod
+        ){
             let op = self.next()?;
-            let right = self.expr8()?;
-            left = Expression::
-                BinaryOperation(
-                    tr_op(op),
-                    Box::new(left),
-                    Box::new(right)
-                );
+            let right = self.expr12()?;
+            left = bin_op(op, left, right);
         }
         Ok(left)
     }
 
-    fn expr8(&mut self) -> Result<Expression, String> {
-        Ok(Expression::Primary(
-            self.parse_primary_highlevel()?
-        ))
+    //'**' is right associative: 2 ** 3 ** 2 is 2 ** 9
+    fn expr12(&mut self) -> Result<Expression, Diagnostic> {
+        let left = self.expr13()?;
+        if let Token::Pow = self.peek()? {
+            let op = self.next()?;
+            let right = self.expr12()?;
+            return Ok(bin_op(op, left, right));
+        }
+        Ok(left)
+    }
+
+    fn expr13(&mut self) -> Result<Expression, Diagnostic> {
+        let primary = self.parse_primary_highlevel()?;
+        let span = primary.span;
+        Ok(Spanned::new(ExpressionKind::Primary(primary), span))
     }
 
     //primary expression
     
-    fn parse_primary_highlevel(&mut self) -> Result<PrimaryExpression, String> {
+    fn parse_primary_highlevel(&mut self) -> Result<PrimaryExpression, Diagnostic> {
         self.parse_post_ops()
     }
 
-    //parses call operator '()' and later get operator '[]'
-    fn parse_post_ops(&mut self) -> Result<PrimaryExpression, String> {
+    //parses call operator '()' and get operator '[]'
+    fn parse_post_ops(&mut self) -> Result<PrimaryExpression, Diagnostic> {
+        let start = self.peek_span()?;
         let mut prim = self.parse_primary()?;
-        while let Token::LeftBracket = self.peek()? {
-            self.eat(Token::LeftBracket)?;
-            let args = self.parse_args()?;
-            self.eat(Token::RightBracket)?;
-            prim = PrimaryExpression::Call(
-                Box::new(prim),
-                args
-            )
+        loop {
+            match self.peek()? {
+                Token::LeftBracket => {
+                    self.eat(Token::LeftBracket)?;
+                    let args = self.parse_args()?;
+                    self.eat(Token::RightBracket)?;
+                    prim = self.spanned(PrimaryExpressionKind::Call(
+                        Box::new(prim),
+                        args
+                    ), start)
+                },
+                Token::LeftSquare => {
+                    self.eat(Token::LeftSquare)?;
+                    let index = self.parse_expression()?;
+                    self.eat(Token::RightSquare)?;
+                    prim = self.spanned(PrimaryExpressionKind::Index(
+                        Box::new(prim),
+                        Box::new(index)
+                    ), start)
+                },
+                _ => break
+            }
         }
         Ok(prim)
     }
 
-    fn parse_primary(&mut self) -> Result<PrimaryExpression, String> {
-        let tok = self.next()?;
-        match tok {
-            Token::Ident(x) => Ok(PrimaryExpression::Ident(x)),
-            Token::Int(x) => Ok(PrimaryExpression::Int(x)),
-            Token::Float(x) => Ok(PrimaryExpression::Float(x)),
-            Token::Str(x) => Ok(PrimaryExpression::Str(x)),
-            Token::True => Ok(PrimaryExpression::Boolean(true)),
-            Token::False => Ok(PrimaryExpression::Boolean(false)),
-            Token::Null => Ok(PrimaryExpression::Null),
+    //the lexer has read the head, expressions and text parts alternate till the tail
+    fn parse_template(&mut self, head: String) -> Result<Vec<TemplatePart>, Diagnostic> {
+        let mut parts = Vec::new();
+        let mut text = head;
+        loop {
+            if !text.is_empty() {
+                parts.push(TemplatePart::Str(text));
+            }
+            parts.push(TemplatePart::Expr(self.parse_expression()?));
+            match self.peek()? {
+                Token::TemplateMiddle(x) => {
+                    self.next()?;
+                    text = x;
+                },
+                Token::TemplateTail(x) => {
+                    self.next()?;
+                    if !x.is_empty() {
+                        parts.push(TemplatePart::Str(x));
+                    }
+                    return Ok(parts);
+                },
+                other => return Err(Diagnostic::syntax(
+                    format!("Expected '}}' to close '${{', not '{:?}'", other),
+                    self.peek_span()?
+                ))
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<PrimaryExpression, Diagnostic> {
+        let start = self.peek_span()?;
+        let tok = self.peek()?;
+        if !starts_primary(&tok) {
+            return Err(Diagnostic::syntax(
+                format!("Unexpected token '{:?}' while parsing primary!", tok),
+                start
+            ));
+        }
+        self.next()?;
+        let kind = match tok {
+            Token::Ident(x) => PrimaryExpressionKind::Ident(x),
+            Token::Int(x) => PrimaryExpressionKind::Int(x),
+            Token::Float(x) => PrimaryExpressionKind::Float(x),
+            Token::Str(x) => PrimaryExpressionKind::Str(x),
+            Token::TemplateHead(x) => PrimaryExpressionKind::Template(self.parse_template(x)?),
+            Token::True => PrimaryExpressionKind::Boolean(true),
+            Token::False => PrimaryExpressionKind::Boolean(false),
+            Token::Null => PrimaryExpressionKind::Null,
             Token::LeftBracket => {
                 let expr = self.parse_expression()?;
                 self.eat(Token::RightBracket)?;
-                Ok(PrimaryExpression::InBrackets(Box::new(expr)))
+                PrimaryExpressionKind::InBrackets(Box::new(expr))
             },
-            Token::Add => Ok(PrimaryExpression::UnaryPlus(
-                Box::new(self.parse_primary()?)
-            )),
-            Token::Sub => Ok(PrimaryExpression::UnaryMinus(
-                Box::new(self.parse_primary()?)
-            )),
-            Token::Not => Ok(PrimaryExpression::UnaryNot(
-                Box::new(self.parse_primary()?)
-            )),
-            _ => Err(format!("Unexpected token '{:?}' while parsing primary!", tok))
-        }
+            Token::LeftSquare => {
+                let items = self.parse_expression_list(Token::RightSquare)?;
+                self.eat(Token::RightSquare)?;
+                PrimaryExpressionKind::Array(items)
+            },
+            Token::LeftBrace => {
+                let entries = self.parse_map_entries()?;
+                self.eat(Token::RightBrace)?;
+                PrimaryExpressionKind::Map(entries)
+            },
+            Token::Add => PrimaryExpressionKind::UnaryPlus(
+                Box::new(self.parse_post_ops()?)
+            ),
+            Token::Sub => PrimaryExpressionKind::UnaryMinus(
+                Box::new(self.parse_post_ops()?)
+            ),
+            Token::Not => PrimaryExpressionKind::UnaryNot(
+                Box::new(self.parse_post_ops()?)
+            ),
+            Token::BitNot => PrimaryExpressionKind::UnaryBitNot(
+                Box::new(self.parse_post_ops()?)
+            ),
+            _ => unreachable!()
+        };
+        Ok(self.spanned(kind, start))
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,135 @@
+use crate::lexer::span::Span;
+
+use std::fmt;
+
+//which layer produced the diagnostic
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorKind {
+    Lexical,
+    Syntax,
+    Runtime,
+    Type,
+    Io,
+    //not an error: script called exit(code)
+    Exit(u8)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note
+}
+
+//one call on the way to a runtime error:
+//span of the call inside the function named here
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraceEntry {
+    pub function: String,
+    pub span: Span
+}
+
+//shared error type of lexer, parser and runner
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub severity: Severity,
+    pub span: Option<Span>,
+    pub message: String,
+    pub notes: Vec<String>,
+    //outermost call first
+    pub traceback: Vec<TraceEntry>
+}
+
+impl Diagnostic {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            kind,
+            severity: Severity::Error,
+            span: None,
+            message: message.into(),
+            notes: Vec::new(),
+            traceback: Vec::new()
+        }
+    }
+
+    pub fn lexical(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Lexical, message).with_span(span)
+    }
+
+    pub fn syntax(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Syntax, message).with_span(span)
+    }
+
+    //builtins don't know where they were called from,
+    //so runtime errors may come without a span
+    pub fn runtime(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Runtime, message)
+    }
+
+    pub fn type_error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Type, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Io, message)
+    }
+
+    pub fn exit(code: u8) -> Diagnostic {
+        Diagnostic::new(ErrorKind::Exit(code), format!("exit with status {}", code))
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    //keeps the more precise span if there is one already
+    pub fn or_span(mut self, span: Span) -> Diagnostic {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Lexical => "lexical",
+            ErrorKind::Syntax => "syntax",
+            ErrorKind::Runtime => "runtime",
+            ErrorKind::Type => "type",
+            ErrorKind::Io => "io",
+            ErrorKind::Exit(_) => "exit"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note"
+        };
+        write!(f, "{}", name)
+    }
+}
+
+//short one-line form, the full one is in render
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.kind, self.message)?;
+        if let Some(span) = self.span {
+            write!(f, " at {}", span)?;
+        }
+        Ok(())
+    }
+}
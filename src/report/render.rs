@@ -0,0 +1,60 @@
+use super::diagnostic::Diagnostic;
+
+//renders diagnostic in rustc-like form:
+//
+//error[syntax]: Expected ';'
+// --> main.lang:3:10
+//  |
+//3 |   echo (1;
+//  |          ^
+//  = note: ...
+//runtime errors inside functions are preceded by a traceback,
+//the innermost call goes last, right before the error itself:
+//
+//traceback (most recent call last):
+//  main.lang:10:1, in <main>
+//    f();
+//  main.lang:3:5, in f
+//    g();
+pub fn render(diagnostic: &Diagnostic, source: &str, name: &str) -> String {
+    let mut out = String::new();
+    if !diagnostic.traceback.is_empty() {
+        out.push_str("traceback (most recent call last):\n");
+        for entry in &diagnostic.traceback {
+            out.push_str(&format!("  {}:{}, in {}\n", name, entry.span, entry.function));
+            if let Some(line) = source.lines().nth(entry.span.line.saturating_sub(1)) {
+                out.push_str(&format!("    {}\n", line.trim()));
+            }
+        }
+    }
+
+    out.push_str(&format!("{}[{}]: {}\n",
+        diagnostic.severity, diagnostic.kind, diagnostic.message));
+    let mut gutter = String::from(" ");
+
+    if let Some(span) = diagnostic.span {
+        let line_number = span.line.to_string();
+        gutter = " ".repeat(line_number.len() + 1);
+        out.push_str(&format!("{}--> {}:{}\n", &gutter[1..], name, span));
+
+        //errors at the end of file may point past the last line
+        let line = source.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+        {
+            let line_rest = line.chars().count().saturating_sub(span.column - 1);
+            let width = source.get(span.start..span.end)
+                .map(|text| text.chars().take_while(|&ch| ch != '\n').count())
+                .unwrap_or(0)
+                .min(line_rest)
+                .max(1);
+            out.push_str(&format!("{}|\n", gutter));
+            out.push_str(&format!("{} | {}\n", line_number, line));
+            out.push_str(&format!("{}| {}{}\n",
+                gutter, " ".repeat(span.column - 1), "^".repeat(width)));
+        }
+    }
+
+    for note in &diagnostic.notes {
+        out.push_str(&format!("{}= note: {}\n", gutter, note));
+    }
+    out
+}
@@ -1,65 +1,226 @@
-use super::object::Object;
-use std::io::stdin;
+use crate::report::diagnostic::Diagnostic;
 
-pub fn echo(object: Object) {
-    println!("{}", match object.to_str() {Object::Str(obj_str) => obj_str, _ => "".to_string()})
+use super::interpreter::Engine;
+use super::object::{array_position, map_key, Object};
+use std::env;
+
+pub fn echo(engine: &mut Engine, object: Object) -> Result<(), Diagnostic> {
+    engine.write_line(&object.to_string())
 }
 
-pub fn print(args: Vec<Object>) -> Result<Object, String> {
-    args.iter().for_each(|x| echo(x.clone()));
+pub fn print(engine: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    for arg in args {
+        echo(engine, arg)?;
+    }
     Ok(Object::Null)
 }
 
-pub fn int(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        None => Err("Expected argument in builtin 'int'!".to_string()),
+pub fn int(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'int'!")),
         Some(val) => Ok(val.to_int())
     }
 }
 
-pub fn float(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        None => Err("Expected argument in builtin 'float'!".to_string()),
+pub fn float(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'float'!")),
         Some(val) => Ok(val.to_float())
     }
 }
 
-pub fn bool_(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        None => Err("Expected argument in builtin 'bool'!".to_string()),
+pub fn bool_(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'bool'!")),
         Some(val) => Ok(val.to_bool())
     }
 }
 
-pub fn string(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        None => Err("Expected argument in builtin 'bool'!".to_string()),
+pub fn string(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'bool'!")),
         Some(val) => Ok(val.to_str())
     }
 }
 
-pub fn object_typeof(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        None => Err("Expected argument in builtin 'typeof'!".to_string()),
-        Some(val) => Ok(match val {
-            Object::Int(_) => Object::Str("int".to_string()),
-            Object::Float(_) => Object::Str("float".to_string()),
-            Object::Boolean(_) => Object::Str("bool".to_string()),
-            Object::Function(_,_) | Object::BuiltIn(_) 
-                => Object::Str("function".to_string()),
-            Object::Null => Object::Str("null".to_string()),
-            Object::Str(_) => Object::Str("string".to_string()),
-        })
+pub fn object_typeof(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'typeof'!")),
+        Some(val) => Ok(Object::Str(val.type_name().to_string()))
+    }
+}
+
+pub fn input(engine: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    print(engine, args)?;
+    let user_input = engine.read_line()?;
+    Ok(Object::Str(user_input.trim_end().to_string()))
+}
+
+//wrong kind of argument is a type error, a missing one is a runtime error
+fn argument_type_error(builtin: &str, expected: &str, got: &Object) -> Diagnostic {
+    Diagnostic::type_error(format!(
+        "Builtin '{}' expects {}, not '{}'", builtin, expected, got.type_name()
+    ))
+}
+
+pub fn len(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        Some(Object::Array(items)) => Ok(Object::Int(items.borrow().len() as i64)),
+        Some(Object::Map(map)) => Ok(Object::Int(map.borrow().len() as i64)),
+        Some(Object::Str(x)) => Ok(Object::Int(x.chars().count() as i64)),
+        Some(other) => Err(argument_type_error("len", "array, map or string", other)),
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'len'!"))
+    }
+}
+
+pub fn push(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (Some(Object::Array(items)), Some(value)) => {
+            items.borrow_mut().push(value.clone());
+            Ok(Object::Int(items.borrow().len() as i64))
+        },
+        (Some(other), Some(_)) => Err(argument_type_error("push", "array", other)),
+        _ => Err(Diagnostic::runtime("Expected array and value in builtin 'push'!"))
+    }
+}
+
+pub fn pop(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        Some(Object::Array(items)) => match items.borrow_mut().pop() {
+            Some(value) => Ok(value),
+            None => Err(Diagnostic::runtime("Can't pop from empty array!"))
+        },
+        Some(other) => Err(argument_type_error("pop", "array", other)),
+        None => Err(Diagnostic::runtime("Expected array in builtin 'pop'!"))
+    }
+}
+
+pub fn insert(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1), args.get(2)) {
+        (Some(Object::Array(items)), Some(index), Some(value)) => {
+            let mut items = items.borrow_mut();
+            //inserting right after the last element is allowed
+            let position = match index {
+                Object::Int(index) if *index == items.len() as i64 => items.len(),
+                _ => array_position(index, items.len())?
+            };
+            items.insert(position, value.clone());
+            Ok(Object::Null)
+        },
+        (Some(other), Some(_), Some(_)) => Err(argument_type_error("insert", "array", other)),
+        _ => Err(Diagnostic::runtime("Expected array, index and value in builtin 'insert'!"))
+    }
+}
+
+pub fn remove(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (Some(Object::Array(items)), Some(index)) => {
+            let mut items = items.borrow_mut();
+            let position = array_position(index, items.len())?;
+            Ok(items.remove(position))
+        },
+        (Some(other), Some(_)) => Err(argument_type_error("remove", "array", other)),
+        _ => Err(Diagnostic::runtime("Expected array and index in builtin 'remove'!"))
+    }
+}
+
+pub fn keys(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        Some(Object::Map(map)) => Ok(Object::new_array(
+            map.borrow().iter().map(|(key, _)| Object::Str(key.clone())).collect()
+        )),
+        Some(other) => Err(argument_type_error("keys", "map", other)),
+        None => Err(Diagnostic::runtime("Expected map in builtin 'keys'!"))
+    }
+}
+
+pub fn values(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        Some(Object::Map(map)) => Ok(Object::new_array(
+            map.borrow().iter().map(|(_, value)| value.clone()).collect()
+        )),
+        Some(other) => Err(argument_type_error("values", "map", other)),
+        None => Err(Diagnostic::runtime("Expected map in builtin 'values'!"))
+    }
+}
+
+pub fn has(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (Some(Object::Map(map)), Some(key)) =>
+            Ok(Object::Boolean(map.borrow().has(&map_key(key)?))),
+        (Some(other), Some(_)) => Err(argument_type_error("has", "map", other)),
+        _ => Err(Diagnostic::runtime("Expected map and key in builtin 'has'!"))
+    }
+}
+
+//returns removed value or null if there was no such key
+pub fn delete(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (Some(Object::Map(map)), Some(key)) =>
+            Ok(map.borrow_mut().remove(&map_key(key)?).unwrap_or(Object::Null)),
+        (Some(other), Some(_)) => Err(argument_type_error("delete", "map", other)),
+        _ => Err(Diagnostic::runtime("Expected map and key in builtin 'delete'!"))
+    }
+}
+
+pub fn copy(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::runtime("Expected argument in builtin 'copy'!")),
+        Some(val) => Ok(val.copy())
+    }
+}
+
+//message is optional
+pub fn assert(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (None, _) => Err(Diagnostic::runtime("Expected condition in builtin 'assert'!")),
+        (Some(condition), _) if condition.is_truthy() => Ok(Object::Null),
+        (Some(condition), None) =>
+            Err(Diagnostic::runtime(format!("Assertion failed: {} is falsy", condition.repr()))),
+        (Some(_), Some(message)) =>
+            Err(Diagnostic::runtime(format!("Assertion failed: {}", message)))
     }
 }
 
-pub fn input(args: Vec<Object>) -> Result<Object, String> {
-    print(args)?;
-    let mut user_input = String::new();
-    match stdin().read_line(&mut user_input) {
-        Ok(_) => { 
-            Ok(Object::Str(user_input.trim_end().to_string())) 
+//compares structurally like '==', third argument is an optional message
+pub fn assert_eq(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match (args.first(), args.get(1)) {
+        (Some(left), Some(right)) if left == right => Ok(Object::Null),
+        (Some(left), Some(right)) => {
+            let mut message = format!("Assertion failed: {} != {}", left.repr(), right.repr());
+            if let Some(extra) = args.get(2) {
+                message.push_str(&format!(" ({})", extra));
+            }
+            Err(Diagnostic::runtime(message))
         },
-        Err(_) => Err("Unexpected error while io reading!".to_string())
+        _ => Err(Diagnostic::runtime("Expected two values in builtin 'assert_eq'!"))
     }
-}
\ No newline at end of file
+}
+
+//command line arguments given to the script after '--'
+pub fn args(engine: &mut Engine, _: Vec<Object>) -> Result<Object, Diagnostic> {
+    Ok(Object::new_array(
+        engine.args().iter().map(|arg| Object::Str(arg.clone())).collect()
+    ))
+}
+
+//null if the variable is not set
+pub fn env_(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        Some(Object::Str(name)) => Ok(match env::var(name) {
+            Ok(value) => Object::Str(value),
+            Err(_) => Object::Null
+        }),
+        _ => Err(Diagnostic::runtime("Expected variable name in builtin 'env'!"))
+    }
+}
+
+//stops the whole program, the launcher turns it into the exit status
+pub fn exit(_: &mut Engine, args: Vec<Object>) -> Result<Object, Diagnostic> {
+    match args.first() {
+        None => Err(Diagnostic::exit(0)),
+        //statuses are limited to one byte by the OS
+        Some(Object::Int(code)) if (0..=255).contains(code) => Err(Diagnostic::exit(*code as u8)),
+        Some(_) => Err(Diagnostic::runtime("Expected int status from 0 to 255 in builtin 'exit'!"))
+    }
+}
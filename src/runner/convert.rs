@@ -0,0 +1,200 @@
+use crate::report::diagnostic::Diagnostic;
+
+use super::map::OrderedMap;
+use super::object::Object;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+//rust values which can be handed to scripts
+pub trait IntoObject {
+    fn into_object(self) -> Object;
+}
+
+//rust values which can be read back from scripts,
+//fails with a type error when the object has another type
+pub trait FromObject: Sized {
+    fn from_object(object: Object) -> Result<Self, Diagnostic>;
+}
+
+fn expected(type_name: &str, object: &Object) -> Diagnostic {
+    Diagnostic::type_error(format!("Expected {}, found {}", type_name, object.type_name()))
+}
+
+impl IntoObject for Object {
+    fn into_object(self) -> Object {
+        self
+    }
+}
+
+impl FromObject for Object {
+    fn from_object(object: Object) -> Result<Object, Diagnostic> {
+        Ok(object)
+    }
+}
+
+impl IntoObject for () {
+    fn into_object(self) -> Object {
+        Object::Null
+    }
+}
+
+impl IntoObject for i64 {
+    fn into_object(self) -> Object {
+        Object::Int(self)
+    }
+}
+
+impl FromObject for i64 {
+    fn from_object(object: Object) -> Result<i64, Diagnostic> {
+        match object {
+            Object::Int(x) => Ok(x),
+            other => Err(expected("int", &other))
+        }
+    }
+}
+
+impl IntoObject for i32 {
+    fn into_object(self) -> Object {
+        Object::Int(i64::from(self))
+    }
+}
+
+impl FromObject for i32 {
+    fn from_object(object: Object) -> Result<i32, Diagnostic> {
+        let x = i64::from_object(object)?;
+        i32::try_from(x).map_err(|_| Diagnostic::runtime(format!("{} doesn't fit in 32 bits", x)))
+    }
+}
+
+impl IntoObject for f64 {
+    fn into_object(self) -> Object {
+        Object::Float(self)
+    }
+}
+
+//ints are accepted where floats are expected, like in arithmetic
+impl FromObject for f64 {
+    fn from_object(object: Object) -> Result<f64, Diagnostic> {
+        match object {
+            Object::Float(x) => Ok(x),
+            Object::Int(x) => Ok(x as f64),
+            other => Err(expected("float", &other))
+        }
+    }
+}
+
+impl IntoObject for bool {
+    fn into_object(self) -> Object {
+        Object::Boolean(self)
+    }
+}
+
+impl FromObject for bool {
+    fn from_object(object: Object) -> Result<bool, Diagnostic> {
+        match object {
+            Object::Boolean(x) => Ok(x),
+            other => Err(expected("bool", &other))
+        }
+    }
+}
+
+impl IntoObject for String {
+    fn into_object(self) -> Object {
+        Object::Str(self)
+    }
+}
+
+impl IntoObject for &str {
+    fn into_object(self) -> Object {
+        Object::Str(self.to_string())
+    }
+}
+
+impl FromObject for String {
+    fn from_object(object: Object) -> Result<String, Diagnostic> {
+        match object {
+            Object::Str(x) => Ok(x),
+            other => Err(expected("string", &other))
+        }
+    }
+}
+
+//none is null
+impl<T: IntoObject> IntoObject for Option<T> {
+    fn into_object(self) -> Object {
+        match self {
+            Some(value) => value.into_object(),
+            None => Object::Null
+        }
+    }
+}
+
+impl<T: FromObject> FromObject for Option<T> {
+    fn from_object(object: Object) -> Result<Option<T>, Diagnostic> {
+        match object {
+            Object::Null => Ok(None),
+            other => T::from_object(other).map(Some)
+        }
+    }
+}
+
+impl<T: IntoObject> IntoObject for Vec<T> {
+    fn into_object(self) -> Object {
+        Object::new_array(self.into_iter().map(IntoObject::into_object).collect())
+    }
+}
+
+//items are converted one by one, the script keeps its array
+impl<T: FromObject> FromObject for Vec<T> {
+    fn from_object(object: Object) -> Result<Vec<T>, Diagnostic> {
+        match object {
+            Object::Array(items) => items.borrow().iter()
+                .map(|item| T::from_object(item.clone()))
+                .collect(),
+            other => Err(expected("array", &other))
+        }
+    }
+}
+
+//hash maps have no order, so keys are sorted to keep output reproducible
+impl<T: IntoObject> IntoObject for HashMap<String, T> {
+    fn into_object(self) -> Object {
+        let mut entries: Vec<(String, T)> = self.into_iter().collect();
+        entries.sort_by(|left, right| left.0.cmp(&right.0));
+        let mut map = OrderedMap::new();
+        for (key, value) in entries {
+            map.insert(key, value.into_object());
+        }
+        Object::new_map(map)
+    }
+}
+
+impl<T: FromObject> FromObject for HashMap<String, T> {
+    fn from_object(object: Object) -> Result<HashMap<String, T>, Diagnostic> {
+        match object {
+            Object::Map(map) => map.borrow().iter()
+                .map(|(key, value)| Ok((key.clone(), T::from_object(value.clone())?)))
+                .collect(),
+            other => Err(expected("map", &other))
+        }
+    }
+}
+
+//what a function registered from rust may return:
+//a plain value or a result with a diagnostic for the script
+pub trait IntoResult {
+    fn into_result(self) -> Result<Object, Diagnostic>;
+}
+
+impl<T: IntoObject> IntoResult for T {
+    fn into_result(self) -> Result<Object, Diagnostic> {
+        Ok(self.into_object())
+    }
+}
+
+impl<T: IntoObject> IntoResult for Result<T, Diagnostic> {
+    fn into_result(self) -> Result<Object, Diagnostic> {
+        self.map(IntoObject::into_object)
+    }
+}
@@ -1,11 +1,41 @@
+use crate::lexer::span::{Span, Spanned};
 use crate::parser::ast::*;
 
-use super::object::Object;
+use std::io::{self, BufRead, BufReader, Write};
+use std::rc::Rc;
+
+use crate::report::diagnostic::{Diagnostic, TraceEntry};
+
+use super::map::OrderedMap;
+use super::object::{map_key, Function, Object};
 use super::storage::MemStack;
 use super::builtins::*;
 
+//deepest nesting of script function calls; each call takes several
+//rust frames, this keeps a debug build within the 8MB main thread stack
+const MAX_CALL_DEPTH: usize = 200;
+
 pub struct Engine {
-    memory: MemStack
+    memory: MemStack,
+    //functions being executed right now, innermost last
+    frames: Vec<Frame>,
+    //command line arguments of the script
+    args: Vec<String>,
+    //where echo, print and input go, stdio unless changed
+    output: Box<dyn Write>,
+    input: Box<dyn BufRead>
+}
+
+struct Frame {
+    function: String,
+    //none when called from rust
+    call_site: Option<Span>
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Engine::new()
+    }
 }
 
 #[derive(Debug)]
@@ -17,36 +47,150 @@ enum Callback {
 
 impl Engine {
     pub fn new() -> Engine {
-        Engine {
-           memory: MemStack::new() 
-        }
+        let mut engine = Engine {
+           memory: MemStack::new(),
+           frames: Vec::new(),
+           args: Vec::new(),
+           output: Box::new(io::stdout()),
+           input: Box::new(BufReader::new(io::stdin()))
+        };
+        engine.init_builtins();
+        engine
     }
 
-    pub fn run(&mut self, ast: &Program) -> Result<(), String> {
-        self.init_builtins();
-        let statements = &ast.0;
-        let maybe_callback = self.visit_statement_list(statements)?;
+    pub fn run(&mut self, ast: &Program) -> Result<(), Diagnostic> {
+        let maybe_callback = self.recovering(|engine| engine.visit_statement_list(&ast.0))?;
         if let Some(Callback::Return(object)) = maybe_callback {
-            echo(object);
+            echo(self, object)?;
         }
         Ok(())
     }
 
+    //runs the program on top of the current state (used by repl)
+    //returns top level 'return' value or value of the trailing expression statement,
+    //assignments and nulls give nothing
+    pub fn eval(&mut self, ast: &Program) -> Result<Option<Object>, Diagnostic> {
+        self.recovering(|engine| engine.eval_statements(&ast.0))
+    }
+
+    //calls function or builtin with already evaluated arguments
+    pub fn call(&mut self, callee: &Object, args: Vec<Object>) -> Result<Object, Diagnostic> {
+        self.recovering(|engine| engine.call_object(callee, args, None))
+    }
+
+    //keeps the engine usable after an error:
+    //drops scopes and frames left open by the failed code, globals stay
+    //(a native may call back and catch the error, so outer frames are kept)
+    fn recovering<T>(&mut self, action: impl FnOnce(&mut Engine) -> Result<T, Diagnostic>) ->
+    Result<T, Diagnostic> {
+        let memory = self.memory.clone();
+        let depth = self.frames.len();
+        let result = action(self);
+        if result.is_err() {
+            self.memory = memory;
+            self.frames.truncate(depth);
+        }
+        result
+    }
+
+    fn eval_statements(&mut self, statements: &[Statement]) -> Result<Option<Object>, Diagnostic> {
+        let mut value = None;
+        for statement in statements {
+            value = None;
+            match &statement.node {
+                StatementKind::ExpressionStmt(expression) => {
+                    let object = self.visit_expression(expression)?;
+                    let is_assign = matches!(&expression.node,
+                        ExpressionKind::BinaryOperation(BinaryOperator::Assign, _, _));
+                    if !is_assign && object != Object::Null {
+                        value = Some(object);
+                    }
+                },
+                _ => if let Some(Callback::Return(object)) = self.visit_statement(statement)? {
+                    return Ok(Some(object));
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    pub fn memory(&self) -> &MemStack {
+        &self.memory
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    //output of echo, print and top level return
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Box::new(input);
+    }
+
+    pub fn write_line(&mut self, text: &str) -> Result<(), Diagnostic> {
+        writeln!(self.output, "{}", text)
+            .map_err(|error| Diagnostic::io(format!("Can't write output: {}", error)))
+    }
+
+    //empty at the end of input
+    //output is flushed first, so prompts are visible
+    pub fn read_line(&mut self) -> Result<String, Diagnostic> {
+        let mut line = String::new();
+        self.output.flush()
+            .and_then(|_| self.input.read_line(&mut line))
+            .map_err(|error| Diagnostic::io(format!("Can't read input: {}", error)))?;
+        Ok(line)
+    }
+
+    //registers rust function (possibly a closure with state) as a global
+    pub fn define_native(&mut self, name: &str,
+    function: impl Fn(&mut Engine, Vec<Object>) -> Result<Object, Diagnostic> + 'static) {
+        self.set_global(name, Object::new_builtin(name, function));
+    }
+
+    //between runs the only scope left is the global one
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.memory.set_or_rewrite_var(name.to_string(), value);
+    }
+
     fn init_builtins(&mut self) {
-        self.memory.set_var("print".to_string(), Object::BuiltIn(print));
-        self.memory.set_var("input".to_string(), Object::BuiltIn(input));
-        self.memory.set_var("int".to_string(), Object::BuiltIn(int));
-        self.memory.set_var("float".to_string(), Object::BuiltIn(float));
-        self.memory.set_var("bool".to_string(), Object::BuiltIn(bool_));
-        self.memory.set_var("typeof".to_string(), Object::BuiltIn(object_typeof));
-        self.memory.set_var("string".to_string(), Object::BuiltIn(string))
+        self.define_native("print", print);
+        self.define_native("input", input);
+        self.define_native("int", int);
+        self.define_native("float", float);
+        self.define_native("bool", bool_);
+        self.define_native("typeof", object_typeof);
+        self.define_native("string", string);
+        self.define_native("len", len);
+        self.define_native("push", push);
+        self.define_native("pop", pop);
+        self.define_native("insert", insert);
+        self.define_native("remove", remove);
+        self.define_native("keys", keys);
+        self.define_native("values", values);
+        self.define_native("has", has);
+        self.define_native("delete", delete);
+        self.define_native("copy", copy);
+        self.define_native("args", args);
+        self.define_native("env", env_);
+        self.define_native("exit", exit);
+        self.define_native("assert", assert);
+        self.define_native("assert_eq", assert_eq);
     }
 
-    fn visit_statement_list(&mut self, statements: &Vec<Statement>) -> 
-    Result<Option<Callback>, String> {
+    fn visit_statement_list(&mut self, statements: &[Statement]) -> 
+    Result<Option<Callback>, Diagnostic> {
         for statement in statements {
             let maybe_callback = self.visit_statement(statement)?;
-            if let Some(_) = maybe_callback {
+            if maybe_callback.is_some() {
                 return Ok(maybe_callback);
             }
         }
@@ -54,125 +198,203 @@ impl Engine {
     }
 
     fn visit_statement(&mut self, statement: &Statement) ->
-    Result<Option<Callback>, String> {
-        match statement {
-            Statement::List(stmts) => self.visit_statement_list(stmts),
-            Statement::Break => Ok(Some(Callback::Break)),
-            Statement::Continue => Ok(Some(Callback::Continue)),
-            Statement::Return(expression) => 
+    Result<Option<Callback>, Diagnostic> {
+        match &statement.node {
+            StatementKind::List(stmts) => self.visit_statement_list(stmts),
+            StatementKind::Break => Ok(Some(Callback::Break)),
+            StatementKind::Continue => Ok(Some(Callback::Continue)),
+            StatementKind::Return(expression) => 
                 Ok(Some(Callback::Return(self.visit_expression(expression)?))),
-            Statement::Echo(expression) => {
-                echo(self.visit_expression(expression)?);
+            StatementKind::Echo(expression) => {
+                let object = self.visit_expression(expression)?;
+                echo(self, object)?;
                 Ok(None)
             },
-            Statement::While(expression, stmt) => 
+            StatementKind::While(expression, stmt) => 
                 self.visit_while(expression, stmt),
-            Statement::If(expression, stmt1, stmt2) =>
+            StatementKind::If(expression, stmt1, stmt2) =>
                 self.visit_if(expression, stmt1, stmt2),
-            Statement::ExpressionStmt(expression) => {
+            StatementKind::ExpressionStmt(expression) => {
                 self.visit_expression(expression)?;
                 Ok(None)
             },
-            Statement::FunctionDecl(name, args, stmt) => {
+            StatementKind::FunctionDecl(name, args, stmt) => {
                 self.visit_func_decl(name, args, stmt)?;
                 Ok(None)
             }
         }
     }
 
-    fn visit_expression(&mut self, expression: &Expression) -> Result<Object, String> {
-        match expression {
-            Expression::Primary(prim) => self.visit_prim(prim),
-            Expression::BinaryOperation(op, expr1, expr2) =>
+    fn visit_expression(&mut self, expression: &Expression) -> Result<Object, Diagnostic> {
+        match &expression.node {
+            ExpressionKind::Primary(prim) => self.visit_prim(prim),
+            ExpressionKind::BinaryOperation(op, expr1, expr2) =>
                 self.visit_bin_op(op, expr1, expr2)
+                    .map_err(|error| error.or_span(expression.span))
         }
     }
 
-    fn visit_prim(&mut self, primary: &PrimaryExpression) -> Result<Object, String> {
-        match primary {
-            PrimaryExpression::UnaryPlus(pr) => 
-                Ok(self.visit_prim(pr)?.unary_plus()),
-            PrimaryExpression::UnaryMinus(pr) =>
-                Ok(self.visit_prim(pr)?.unary_minus()),
-            PrimaryExpression::UnaryNot(pr) =>
+    fn visit_prim(&mut self, primary: &PrimaryExpression) -> Result<Object, Diagnostic> {
+        match &primary.node {
+            PrimaryExpressionKind::UnaryPlus(pr) => 
+                self.visit_prim(pr)?.unary_plus()
+                    .map_err(|error| error.or_span(primary.span)),
+            PrimaryExpressionKind::UnaryMinus(pr) =>
+                self.visit_prim(pr)?.unary_minus()
+                    .map_err(|error| error.or_span(primary.span)),
+            PrimaryExpressionKind::UnaryNot(pr) =>
                 Ok(self.visit_prim(pr)?.not()),
-            PrimaryExpression::InBrackets(expr) =>
+            PrimaryExpressionKind::UnaryBitNot(pr) =>
+                self.visit_prim(pr)?.bit_not()
+                    .map_err(|error| error.or_span(primary.span)),
+            PrimaryExpressionKind::InBrackets(expr) =>
                 self.visit_expression(expr),
-            PrimaryExpression::Ident(name) => 
-                Ok(self.memory.get_var(name.clone())),
-            PrimaryExpression::Float(x) =>
+            PrimaryExpressionKind::Ident(name) => 
+                Ok(self.memory.get_var(name)),
+            PrimaryExpressionKind::Float(x) =>
                 Ok(Object::Float(*x)),
-            PrimaryExpression::Int(x) =>
+            PrimaryExpressionKind::Int(x) =>
                 Ok(Object::Int(*x)),
-            PrimaryExpression::Str(x) => 
+            PrimaryExpressionKind::Str(x) => 
                 Ok(Object::Str(x.clone())),
-            PrimaryExpression::Boolean(x) =>
+            PrimaryExpressionKind::Template(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        TemplatePart::Str(x) => text.push_str(x),
+                        //the same text string() gives
+                        TemplatePart::Expr(expression) =>
+                            text.push_str(&self.visit_expression(expression)?.to_string())
+                    }
+                }
+                Ok(Object::Str(text))
+            },
+            PrimaryExpressionKind::Boolean(x) =>
                 Ok(Object::Boolean(*x)),
-            PrimaryExpression::Null =>
+            PrimaryExpressionKind::Null =>
                 Ok(Object::Null),
-            PrimaryExpression::Call(call_object, args) =>
-                self.visit_func_call(call_object, args)
+            PrimaryExpressionKind::Call(call_object, args) =>
+                self.visit_func_call(call_object, args, primary.span)
+                    .map_err(|error| error.or_span(primary.span)),
+            PrimaryExpressionKind::Array(items) => {
+                let mut values: Vec<Object> = Vec::new();
+                for item in items {
+                    values.push(self.visit_expression(item)?);
+                }
+                Ok(Object::new_array(values))
+            },
+            PrimaryExpressionKind::Map(entries) => {
+                let mut map = OrderedMap::new();
+                for (key, value) in entries {
+                    let key_span = key.span;
+                    let key = map_key(&self.visit_expression(key)?)
+                        .map_err(|error| error.with_span(key_span))?;
+                    map.insert(key, self.visit_expression(value)?);
+                }
+                Ok(Object::new_map(map))
+            },
+            PrimaryExpressionKind::Index(object, index) => {
+                let object = self.visit_prim(object)?;
+                let index = self.visit_expression(index)?;
+                object.get_item(&index)
+                    .map_err(|error| error.or_span(primary.span))
+            }
         }
     }
 
     fn visit_bin_op(&mut self, operator: &BinaryOperator, 
-    left: &Box<Expression>, right: &Box<Expression>) -> Result<Object, String> {
+    left: &Expression, right: &Expression) -> Result<Object, Diagnostic> {
         match operator {
             BinaryOperator::Assign => {
-                match &**left {
-                    Expression::Primary(PrimaryExpression::Ident(name)) 
-                    => {
+                match &left.node {
+                    ExpressionKind::Primary(Spanned {
+                        node: PrimaryExpressionKind::Ident(name), ..
+                    }) => {
                         let val = self.visit_expression(right)?;
                         self.memory.set_or_rewrite_var(name.clone(), val.clone());
                         Ok(val)
                     },
-                    _ => {Err("Can't assign to a constant".to_string()) }
+                    ExpressionKind::Primary(Spanned {
+                        node: PrimaryExpressionKind::Index(object, index), ..
+                    }) => {
+                        let object = self.visit_prim(object)?;
+                        let index = self.visit_expression(index)?;
+                        let val = self.visit_expression(right)?;
+                        object.set_item(&index, val.clone())
+                            .map_err(|error| error.or_span(left.span))?;
+                        Ok(val)
+                    },
+                    _ => Err(Diagnostic::runtime("Can't assign to a constant")
+                        .with_span(left.span))
                 }
             },
 
             BinaryOperator::Add => 
-            Ok(self.visit_expression(left)?.add(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.add(&self.visit_expression(right)?),
             BinaryOperator::Sub => 
-            Ok(self.visit_expression(left)?.sub(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.sub(&self.visit_expression(right)?),
             BinaryOperator::Mul => 
-            Ok(self.visit_expression(left)?.mul(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.mul(&self.visit_expression(right)?),
             BinaryOperator::Div => 
-            Ok(self.visit_expression(left)?.div(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.div(&self.visit_expression(right)?),
             BinaryOperator::Mod => 
-            Ok(self.visit_expression(left)?.mod_(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.mod_(&self.visit_expression(right)?),
+            BinaryOperator::FloorDiv => 
+            self.visit_expression(left)?.floor_div(&self.visit_expression(right)?),
+            BinaryOperator::Pow => 
+            self.visit_expression(left)?.pow(&self.visit_expression(right)?),
+
+            BinaryOperator::BitAnd => 
+            self.visit_expression(left)?.bit_and(&self.visit_expression(right)?),
+            BinaryOperator::BitOr => 
+            self.visit_expression(left)?.bit_or(&self.visit_expression(right)?),
+            BinaryOperator::BitXor => 
+            self.visit_expression(left)?.bit_xor(&self.visit_expression(right)?),
+            BinaryOperator::ShiftLeft => 
+            self.visit_expression(left)?.shift_left(&self.visit_expression(right)?),
+            BinaryOperator::ShiftRight => 
+            self.visit_expression(left)?.shift_right(&self.visit_expression(right)?),
             
 
             BinaryOperator::Equal => 
             Ok(self.visit_expression(left)?.equal(&self.visit_expression(right)?)),
             BinaryOperator::NotEqual => 
             Ok(self.visit_expression(left)?.not_equal(&self.visit_expression(right)?)),
+            BinaryOperator::Is => 
+            Ok(self.visit_expression(left)?.is(&self.visit_expression(right)?)),
             BinaryOperator::Greater => 
-            Ok(self.visit_expression(left)?.greater(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.greater(&self.visit_expression(right)?),
             BinaryOperator::Less => 
-            Ok(self.visit_expression(left)?.less(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.less(&self.visit_expression(right)?),
             BinaryOperator::GreaterOrEqual => 
-            Ok(self.visit_expression(left)?.greater_or_equal(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.greater_or_equal(&self.visit_expression(right)?),
             BinaryOperator::LessOrEqual => 
-            Ok(self.visit_expression(left)?.less_or_equal(&self.visit_expression(right)?)),
+            self.visit_expression(left)?.less_or_equal(&self.visit_expression(right)?),
             
-            BinaryOperator::And => 
-            Ok(self.visit_expression(left)?.and(&self.visit_expression(right)?)),
-            BinaryOperator::Or => 
-            Ok(self.visit_expression(left)?.or(&self.visit_expression(right)?)),
+            //right side is evaluated only when it decides the result:
+            //'&&' gives the first falsy operand, '||' the first truthy one
+            BinaryOperator::And => {
+                let left = self.visit_expression(left)?;
+                if left.is_truthy() { self.visit_expression(right) } else { Ok(left) }
+            },
+            BinaryOperator::Or => {
+                let left = self.visit_expression(left)?;
+                if left.is_truthy() { Ok(left) } else { self.visit_expression(right) }
+            },
             
             BinaryOperator::StrAdd =>
             Ok(self.visit_expression(left)?.str_add(&self.visit_expression(right)?)),
 
             BinaryOperator::Not => 
-            Err("Unexpected 'not' operator!".to_string())
+            Err(Diagnostic::runtime("Unexpected 'not' operator!"))
             
         }
     }
 
-    fn visit_while(&mut self, expression: &Expression, statement: &Box<Statement>) ->
-    Result<Option<Callback>, String> {
+    fn visit_while(&mut self, expression: &Expression, statement: &Statement) ->
+    Result<Option<Callback>, Diagnostic> {
         self.memory.new_scope();
-        while let Object::Boolean(true) = self.visit_expression(expression)? {
+        while self.visit_expression(expression)?.is_truthy() {
             if let Some(callback) = self.visit_statement(statement)? {
                 match callback {
                     Callback::Continue => (),
@@ -186,66 +408,105 @@ impl Engine {
     }
 
     fn visit_if(&mut self, expression: &Expression, 
-    first_statement: &Box<Statement>, second_statement: &Option<Box<Statement>>) ->
-    Result<Option<Callback>, String> {
+    first_statement: &Statement, second_statement: &Option<Box<Statement>>) ->
+    Result<Option<Callback>, Diagnostic> {
         self.memory.new_scope();
-        if let Object::Boolean(true) = self.visit_expression(expression)? {
+        if self.visit_expression(expression)?.is_truthy() {
             let maybe_callback = self.visit_statement(first_statement);
             self.memory.leave_scope();
             maybe_callback
         }
+        else if let Some(second) = second_statement {
+            let maybe_callback = self.visit_statement(second);
+            self.memory.leave_scope();
+            maybe_callback
+        }
         else {
-            if let Some(second) = second_statement {
-                let maybe_callback = self.visit_statement(second);
-                self.memory.leave_scope();
-                maybe_callback
-            }
-            else {
-                self.memory.leave_scope();
-                Ok(None)
-            }
+            self.memory.leave_scope();
+            Ok(None)
         }
     }
 
-    fn visit_func_decl(&mut self, name: &String, args: &Vec<String>, 
-    statement: &Box<Statement>) -> Result<(), String> {
-        let f_object = Object::Function(args.clone(), statement.clone());
-        self.memory.set_or_rewrite_var(name.clone(), f_object);
+    fn visit_func_decl(&mut self, name: &str, args: &[String], 
+    statement: &Statement) -> Result<(), Diagnostic> {
+        let function = Function {
+            name: name.to_string(),
+            params: args.to_vec(),
+            body: statement.clone()
+        };
+        let closure = self.memory.capture(name);
+        self.memory.set_or_rewrite_var(name.to_string(), Object::Function(Rc::new(function), closure));
         Ok(())
     }
 
-    fn visit_func_call(&mut self, call_object: &Box<PrimaryExpression>, 
-    call_args: &Vec<Expression>) -> Result<Object, String> {
-        match self.visit_prim(call_object)? {
-            Object::Function(func_args, body) => {
+    //the first frame to see an error is the innermost one,
+    //so it records the whole call stack
+    fn attach_traceback(&self, mut error: Diagnostic) -> Diagnostic {
+        if error.traceback.is_empty() {
+            let callers = std::iter::once("<main>")
+                .chain(self.frames.iter().map(|frame| &frame.function[..]));
+            error.traceback = self.frames.iter()
+                .zip(callers)
+                .filter_map(|(frame, caller)| frame.call_site.map(|span| TraceEntry {
+                    function: caller.to_string(),
+                    span
+                }))
+                .collect();
+        }
+        error
+    }
+
+    fn visit_func_call(&mut self, call_object: &PrimaryExpression, 
+    call_args: &[Expression], call_site: Span) -> Result<Object, Diagnostic> {
+        let callee = self.visit_prim(call_object)?;
+        if !matches!(callee, Object::Function(..) | Object::BuiltIn(_)) {
+            return Err(Diagnostic::runtime(format!("Can't call '{:?}' object!", call_object.node))
+                .with_span(call_object.span)
+                .with_note(format!("value is {}", callee)));
+        }
+        //arguments are evaluated in the caller's scope
+        let mut values: Vec<Object> = Vec::new();
+        for c_arg in call_args {
+            values.push(self.visit_expression(c_arg)?);
+        }
+        self.call_object(&callee, values, Some(call_site))
+    }
+
+    fn call_object(&mut self, callee: &Object, values: Vec<Object>,
+    call_site: Option<Span>) -> Result<Object, Diagnostic> {
+        match callee {
+            Object::Function(function, closure) => {
+                if self.frames.len() >= MAX_CALL_DEPTH {
+                    return Err(Diagnostic::runtime("Maximum recursion depth exceeded")
+                        .with_note(format!("calls can be nested at most {} deep", MAX_CALL_DEPTH)));
+                }
+                //body runs in the scope where the function was declared
+                let closure = self.memory.with_closure(closure);
+                let caller_memory = std::mem::replace(&mut self.memory, closure);
                 self.memory.new_scope();
-                for (f_arg, c_arg) in func_args.iter().zip(call_args.iter()) {
-                    let value = self.visit_expression(c_arg)?;
-                    self.memory.set_var(f_arg.clone(), value);
+                let mut values = values.into_iter();
+                for f_arg in &function.params {
+                    self.memory.set_var(f_arg.clone(), values.next().unwrap_or(Object::Null));
                 }
-                
-                let result = match self.visit_statement(&body)? {
+
+                self.frames.push(Frame {
+                    function: function.name.clone(),
+                    call_site
+                });
+                let result = self.visit_statement(&function.body)
+                    .map_err(|error| self.attach_traceback(error));
+                self.frames.pop();
+                self.memory = caller_memory;
+                match result? {
                     Some(callback) => match callback {
                         Callback::Return(object) => Ok(object),
                         Callback::Break | Callback::Continue => Ok(Object::Null)
                     },
                     None => Ok(Object::Null)
-                };
-                self.memory.leave_scope();
-                result
-            },
-            Object::BuiltIn(builtin_function) => {
-                let arguments_map = call_args
-                    .iter()
-                    .map(|argument| self.visit_expression(argument));
-                let mut arguments: Vec<Object> = Vec::new();
-                for maybe_arg in arguments_map {
-                    arguments.push(maybe_arg?);
                 }
-                let builtin_result = builtin_function(arguments);
-                builtin_result
-            }
-            _ => Err(format!("Can't call '{:?}' object!", call_object))
+            },
+            Object::BuiltIn(builtin) => (builtin.function)(self, values),
+            other => Err(Diagnostic::runtime(format!("Can't call {} value!", other.type_name())))
         }
     }
 
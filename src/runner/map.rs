@@ -0,0 +1,56 @@
+use super::object::Object;
+use std::collections::HashMap;
+
+//string keyed map which remembers insertion order,
+//so printing and iterating give the same result every run
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap {
+    keys: Vec<String>,
+    values: HashMap<String, Object>
+}
+
+impl OrderedMap {
+    pub fn new() -> OrderedMap {
+        OrderedMap::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Object> {
+        self.values.get(key)
+    }
+
+    pub fn insert(&mut self, key: String, value: Object) {
+        if !self.values.contains_key(&key) {
+            self.keys.push(key.clone());
+        }
+        self.values.insert(key, value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Object> {
+        let value = self.values.remove(key)?;
+        self.keys.retain(|k| k != key);
+        Some(value)
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Object)> {
+        self.keys.iter().map(move |key| (key, &self.values[key]))
+    }
+}
+
+//order doesn't matter for equality
+impl PartialEq for OrderedMap {
+    fn eq(&self, other: &OrderedMap) -> bool {
+        self.values == other.values
+    }
+}
@@ -1,4 +1,7 @@
 pub mod interpreter;
 pub mod object;
 pub mod storage;
-pub mod builtins;
\ No newline at end of file
+pub mod builtins;
+pub mod map;
+pub mod convert;
+pub mod streams;
@@ -1,29 +1,250 @@
 use crate::parser::ast::*;
+use crate::report::diagnostic::Diagnostic;
 
-#[derive(Debug, PartialEq, Clone)]
+use super::interpreter::Engine;
+use super::map::OrderedMap;
+use super::storage::Closure;
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+//code of a user defined function, the scopes it captured go with each value
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Statement
+}
+
+//only the signature, the body can be long
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+//signature shared by builtins and functions registered from rust,
+//the engine is there to call back into the script
+pub type NativeFn = dyn Fn(&mut Engine, Vec<Object>) -> Result<Object, Diagnostic>;
+
+//function implemented in rust, may keep its own state
+pub struct BuiltIn {
+    pub name: String,
+    pub function: Box<NativeFn>
+}
+
+impl fmt::Debug for BuiltIn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BuiltIn")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Object {
-    Int(i32),
+    Int(i64),
     Float(f64),
     Str(String),
     Boolean(bool),
-    Function(Vec<String>, Box<Statement>),
-    BuiltIn(fn(Vec<Object>) -> Result<Object, String>),
+    Function(Rc<Function>, Closure),
+    BuiltIn(Rc<BuiltIn>),
+    //shared, so builtins like push can change it in place
+    Array(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<RefCell<OrderedMap>>),
     Null
 }
 
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
+        self.eq_seen(other, &mut Vec::new())
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_seen(f, &mut Vec::new())
+    }
+}
+
 impl Object {
+    //the same collection is always equal to itself, seen holds pairs of
+    //collections being compared right now, meeting a pair again is a cycle
+    fn eq_seen(&self, other: &Object, seen: &mut Vec<(usize, usize)>) -> bool {
+        if let (Some(left), Some(right)) = (self.address(), other.address()) {
+            if left == right || seen.contains(&(left, right)) {
+                return true;
+            }
+            seen.push((left, right));
+            let result = self.eq_value(other, seen);
+            seen.pop();
+            return result;
+        }
+        self.eq_value(other, seen)
+    }
+
+    fn eq_value(&self, other: &Object, seen: &mut Vec<(usize, usize)>) -> bool {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => left == right,
+            (Object::Float(left), Object::Float(right)) => left == right,
+            (Object::Str(left), Object::Str(right)) => left == right,
+            (Object::Boolean(left), Object::Boolean(right)) => left == right,
+            (Object::Function(left, _), Object::Function(right, _)) => Rc::ptr_eq(left, right),
+            (Object::BuiltIn(left), Object::BuiltIn(right)) => Rc::ptr_eq(left, right),
+            //collections are compared by contents
+            (Object::Array(left), Object::Array(right)) => {
+                let (left, right) = (left.borrow(), right.borrow());
+                left.len() == right.len()
+                    && left.iter().zip(right.iter()).all(|(l, r)| l.eq_seen(r, seen))
+            },
+            //order doesn't matter for maps
+            (Object::Map(left), Object::Map(right)) => {
+                let (left, right) = (left.borrow(), right.borrow());
+                left.len() == right.len()
+                    && left.iter().all(|(key, l)| match right.get(key) {
+                        Some(r) => l.eq_seen(r, seen),
+                        None => false
+                    })
+            },
+            (Object::Null, Object::Null) => true,
+            _ => false
+        }
+    }
+
+    pub fn new_array(items: Vec<Object>) -> Object {
+        Object::Array(Rc::new(RefCell::new(items)))
+    }
+
+    pub fn new_builtin(name: &str,
+    function: impl Fn(&mut Engine, Vec<Object>) -> Result<Object, Diagnostic> + 'static) -> Object {
+        Object::BuiltIn(Rc::new(BuiltIn {
+            name: name.to_string(),
+            function: Box::new(function)
+        }))
+    }
+
+    pub fn new_map(map: OrderedMap) -> Object {
+        Object::Map(Rc::new(RefCell::new(map)))
+    }
+
+    //address of the shared storage, None for plain values
+    fn address(&self) -> Option<usize> {
+        match self {
+            Object::Array(items) => Some(Rc::as_ptr(items) as *const () as usize),
+            Object::Map(map) => Some(Rc::as_ptr(map) as *const () as usize),
+            Object::Function(function, _) => Some(Rc::as_ptr(function) as *const () as usize),
+            _ => None
+        }
+    }
+
+    //seen holds collections being printed right now,
+    //so a collection which contains itself is printed as [...]
+    fn fmt_seen(&self, f: &mut fmt::Formatter, seen: &mut Vec<usize>) -> fmt::Result {
+        if let Some(address) = self.address() {
+            if seen.contains(&address) {
+                return match self {
+                    Object::Map(_) => write!(f, "{{...}}"),
+                    _ => write!(f, "[...]")
+                };
+            }
+            seen.push(address);
+        }
+        let result = self.fmt_value(f, seen);
+        if self.address().is_some() {
+            seen.pop();
+        }
+        result
+    }
+
+    fn fmt_value(&self, f: &mut fmt::Formatter, seen: &mut Vec<usize>) -> fmt::Result {
+        match self {
+            Object::Int(x) => write!(f, "{}", x),
+            Object::Float(x) => write!(f, "{}", x),
+            Object::Str(x) => write!(f, "{}", x),
+            Object::Boolean(x) => write!(f, "{}", if *x {"true"} else {"false"}),
+            Object::Function(..) => write!(f, "function"),
+            Object::Null => write!(f, "null"),
+            Object::BuiltIn(_) => write!(f, "builtin function"),
+            Object::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_nested(f, seen)?;
+                }
+                write!(f, "]")
+            },
+            Object::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    value.fmt_nested(f, seen)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+
+    //strings inside collections are quoted, so ["1"] differs from [1]
+    fn fmt_nested(&self, f: &mut fmt::Formatter, seen: &mut Vec<usize>) -> fmt::Result {
+        match self {
+            Object::Str(x) => write!(f, "{:?}", x),
+            _ => self.fmt_seen(f, seen)
+        }
+    }
+
+    //like display, but strings are quoted, used in messages
+    pub fn repr(&self) -> String {
+        match self {
+            Object::Str(x) => format!("{:?}", x),
+            _ => self.to_string()
+        }
+    }
+
+    //shallow copy: a new collection with the same items
+    pub fn copy(&self) -> Object {
+        match self {
+            Object::Array(items) => Object::new_array(items.borrow().clone()),
+            Object::Map(map) => Object::new_map(map.borrow().clone()),
+            _ => self.clone()
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Int(_) => "int",
+            Object::Float(_) => "float",
+            Object::Boolean(_) => "bool",
+            Object::Function(..) | Object::BuiltIn(_) => "function",
+            Object::Null => "null",
+            Object::Str(_) => "string",
+            Object::Array(_) => "array",
+            Object::Map(_) => "map"
+        }
+    }
+
     pub fn to_int(&self) -> Object {
         match self {
             Object::Int(x) => Object::Int(*x),
-            Object::Float(x) => Object::Int(*x as i32),
-            Object::Str(x) => match x.parse::<i32>() {
+            Object::Float(x) => float_to_int(*x),
+            Object::Str(x) => match x.parse::<i64>() {
                 Ok(integer) => Object::Int(integer),
                 Err(_) => Object::Null
             },
-            Object::Boolean(x) => Object::Int(*x as i32),
-            Object::Function(_, _) => Object::Null,
+            Object::Boolean(x) => Object::Int(i64::from(*x)),
+            Object::Function(..) => Object::Null,
             Object::Null => Object::Null,
-            Object::BuiltIn(_) => Object::Null
+            Object::BuiltIn(_) => Object::Null,
+            Object::Array(_) => Object::Null,
+            Object::Map(_) => Object::Null
         }
     }
 
@@ -35,96 +256,200 @@ impl Object {
                 Ok(floating) => Object::Float(floating),
                 Err(_) => Object::Null
             },
-            Object::Boolean(x) => Object::Float(*x as i32 as f64),
-            Object::Function(_, _) => Object::Null,
+            Object::Boolean(x) => Object::Float(f64::from(u8::from(*x))),
+            Object::Function(..) => Object::Null,
             Object::Null => Object::Null,
-            Object::BuiltIn(_) => Object::Null
+            Object::BuiltIn(_) => Object::Null,
+            Object::Array(_) => Object::Null,
+            Object::Map(_) => Object::Null
         }
     }
 
     pub fn to_str(&self) -> Object {
+        Object::Str(self.to_string())
+    }
+
+    //used by conditions, logical operators and bool():
+    //false, null, zero, empty string and empty collections are falsy
+    pub fn is_truthy(&self) -> bool {
         match self {
-            Object::Int(x) => Object::Str(x.to_string()),
-            Object::Float(x) => Object::Str(x.to_string()),
-            Object::Str(x) => Object::Str(x.clone()),
-            Object::Boolean(x) => Object::Str(String::from(if *x {"true"} else {"false"})),
-            Object::Function(_,_) => Object::Str(String::from("function")),
-            Object::Null => Object::Str(String::from("null")),
-            Object::BuiltIn(_) => Object::Str(String::from("builtin function"))
+            Object::Boolean(x) => *x,
+            Object::Null => false,
+            Object::Int(x) => *x != 0,
+            Object::Float(x) => *x != 0f64,
+            Object::Str(x) => !x.is_empty(),
+            Object::Array(items) => !items.borrow().is_empty(),
+            Object::Map(map) => !map.borrow().is_empty(),
+            Object::Function(..) | Object::BuiltIn(_) => true
         }
     }
 
+    //bool() follows the same rules as conditions
     pub fn to_bool(&self) -> Object {
-        match self {
-            Object::Int(x) => Object::Boolean(*x > 0),
-            Object::Float(x) => Object::Boolean(*x > 0f64),
-            Object::Str(x) => Object::Boolean(&x[..] == "true"),
-            Object::Boolean(x) => Object::Boolean(*x),
-            Object::Function(_,_) => Object::Null,
-            Object::Null => Object::Boolean(false),
-            Object::BuiltIn(_) => Object::Null
-        }
+        Object::Boolean(self.is_truthy())
     }
 
     //operators
 
-    pub fn add(&self, other: &Object) -> Object {
+    pub fn add(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Int(left + right),
-            (Object::Float(left), Object::Float(right)) => Object::Float(left + right),
-            (Object::Int(left), Object::Float(right)) => Object::Float(*left as f64 + right),
-            (Object::Float(left), Object::Int(right)) => Object::Float(left + *right as f64),
-            (Object::Str(left), Object::Str(right)) => Object::Str(format!("{}{}", left, right)),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => left.checked_add(*right)
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error("+", *left, *right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left + right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float(*left as f64 + right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left + *right as f64)),
+            (Object::Str(left), Object::Str(right)) => Ok(Object::Str(format!("{}{}", left, right))),
+            _ => Err(binary_type_error("+", self, other))
         }
     }
 
-    pub fn sub(&self, other: &Object) -> Object {
+    pub fn sub(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Int(left - right),
-            (Object::Float(left), Object::Float(right)) => Object::Float(left - right),
-            (Object::Int(left), Object::Float(right)) => Object::Float(*left as f64 - right),
-            (Object::Float(left), Object::Int(right)) => Object::Float(left - *right as f64),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => left.checked_sub(*right)
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error("-", *left, *right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left - right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float(*left as f64 - right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left - *right as f64)),
+            _ => Err(binary_type_error("-", self, other))
         }
     }
 
     pub fn str_add(&self, other: &Object) -> Object {
-        self.to_str().add(&other.to_str())
+        Object::Str(format!("{}{}", self, other))
     }
 
-    pub fn mul(&self, other: &Object) -> Object {
+    pub fn mul(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Int(left * right),
-            (Object::Float(left), Object::Float(right)) => Object::Float(left * right),
-            (Object::Int(left), Object::Float(right)) => Object::Float(*left as f64 * right),
-            (Object::Float(left), Object::Int(right)) => Object::Float(left * *right as f64),
-            (Object::Int(left), Object::Str(right)) => Object::Str(mul_str(*left, right)),
-            (Object::Str(left), Object::Int(right)) => Object::Str(mul_str(*right, left)),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => left.checked_mul(*right)
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error("*", *left, *right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left * right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float(*left as f64 * right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left * *right as f64)),
+            (Object::Int(left), Object::Str(right)) => Ok(Object::Str(mul_str(*left, right))),
+            (Object::Str(left), Object::Int(right)) => Ok(Object::Str(mul_str(*right, left))),
+            _ => Err(binary_type_error("*", self, other))
         }
     }
 
-    pub fn div(&self, other: &Object) -> Object {
+    pub fn div(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            //(Object::Int(left), Object::Int(right)) => Object::Int(left / right),
-            (Object::Int(left), Object::Int(right)) => Object::Float(*left as f64 / *right as f64),
-            (Object::Float(left), Object::Float(right)) => Object::Float(left / right),
-            (Object::Int(left), Object::Float(right)) => Object::Float(*left as f64 / right),
-            (Object::Float(left), Object::Int(right)) => Object::Float(left / *right as f64),
+            //(Object::Int(left), Object::Int(right)) => Ok(Object::Int(left / right)),
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Float(*left as f64 / *right as f64)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left / right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float(*left as f64 / right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left / *right as f64)),
 
-            _ => Object::Null
+            _ => Err(binary_type_error("/", self, other))
         }
     }
 
-    pub fn mod_(&self, other: &Object) -> Object {
+    pub fn mod_(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Int(left % right),
-            (Object::Float(left), Object::Float(right)) => Object::Float(left % right),
-            (Object::Int(left), Object::Float(right)) => Object::Float(*left as f64 % right),
-            (Object::Float(left), Object::Int(right)) => Object::Float(left % *right as f64),
+            (Object::Int(_), Object::Int(0)) => Err(Diagnostic::runtime("Division by zero")),
+            //the remainder always fits, only i64::MIN % -1 overflows while dividing
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Int(left.wrapping_rem(*right))),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left % right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float(*left as f64 % right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left % *right as f64)),
 
-            _ => Object::Null
+            _ => Err(binary_type_error("%", self, other))
+        }
+    }
+
+    //rounds towards negative infinity, so -7 // 2 is -4
+    pub fn floor_div(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(_), Object::Int(0)) => Err(Diagnostic::runtime("Division by zero")),
+            (Object::Int(left), Object::Int(right)) => {
+                let quotient = left.checked_div(*right)
+                    .ok_or_else(|| overflow_error("//", *left, *right))?;
+                if left % right != 0 && (*left < 0) != (*right < 0) {
+                    Ok(Object::Int(quotient - 1))
+                }
+                else {
+                    Ok(Object::Int(quotient))
+                }
+            },
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float((left / right).floor())),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float((*left as f64 / right).floor())),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float((left / *right as f64).floor())),
+
+            _ => Err(binary_type_error("//", self, other))
+        }
+    }
+
+    //int to a negative power gives float, like 2 ** -1 is 0.5
+    pub fn pow(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) if *right < 0 =>
+                Ok(Object::Float((*left as f64).powf(*right as f64))),
+            (Object::Int(left), Object::Int(right)) => u32::try_from(*right).ok()
+                .and_then(|exponent| left.checked_pow(exponent))
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error("**", *left, *right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left.powf(*right))),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Float((*left as f64).powf(*right))),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Float(left.powf(*right as f64))),
+
+            _ => Err(binary_type_error("**", self, other))
+        }
+    }
+
+    //bitwise operators work on ints only
+
+    pub fn bit_and(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Int(left & right)),
+            _ => Err(binary_type_error("&", self, other))
+        }
+    }
+
+    pub fn bit_or(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Int(left | right)),
+            _ => Err(binary_type_error("|", self, other))
+        }
+    }
+
+    pub fn bit_xor(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Int(left ^ right)),
+            _ => Err(binary_type_error("^", self, other))
+        }
+    }
+
+    //bits shifted out of the int are an overflow, like in '*'
+    pub fn shift_left(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => {
+                let shift = shift_amount(*right)?;
+                let result = left << shift;
+                if result >> shift == *left {
+                    Ok(Object::Int(result))
+                }
+                else {
+                    Err(overflow_error("<<", *left, *right))
+                }
+            },
+            _ => Err(binary_type_error("<<", self, other))
+        }
+    }
+
+    //keeps the sign: -8 >> 1 is -4
+    pub fn shift_right(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Int(left >> shift_amount(*right)?)),
+            _ => Err(binary_type_error(">>", self, other))
+        }
+    }
+
+    pub fn bit_not(&self) -> Result<Object, Diagnostic> {
+        match self {
+            Object::Int(x) => Ok(Object::Int(!x)),
+            _ => Err(unary_type_error("~", self))
         }
     }
 
@@ -136,92 +461,193 @@ impl Object {
         Object::Boolean(self != other)
     }
 
-    pub fn greater(&self, other: &Object) -> Object {
-        match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Boolean(left > right),
-            (Object::Float(left), Object::Float(right)) => Object::Boolean(left > right),
-            (Object::Int(left), Object::Float(right)) => Object::Boolean(*left as f64 > *right),
-            (Object::Float(left), Object::Int(right)) => Object::Boolean(*left > *right as f64),
-            (Object::Str(left), Object::Str(right)) => Object::Boolean(left > right),
-            _ => Object::Null
+    //identity: collections and functions must be the very same object,
+    //plain values are compared by value
+    pub fn is(&self, other: &Object) -> Object {
+        match (self.address(), other.address()) {
+            (Some(left), Some(right)) => Object::Boolean(left == right),
+            (None, None) => Object::Boolean(self == other),
+            _ => Object::Boolean(false)
         }
     }
 
-    pub fn less(&self, other: &Object) -> Object {
+    pub fn greater(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Boolean(left < right),
-            (Object::Float(left), Object::Float(right)) => Object::Boolean(left < right),
-            (Object::Int(left), Object::Float(right)) => Object::Boolean((*left as f64) < *right),
-            (Object::Float(left), Object::Int(right)) => Object::Boolean(*left < *right as f64),
-            (Object::Str(left), Object::Str(right)) => Object::Boolean(left < right),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Boolean(left > right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Boolean(left > right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Boolean(*left as f64 > *right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Boolean(*left > *right as f64)),
+            (Object::Str(left), Object::Str(right)) => Ok(Object::Boolean(left > right)),
+            _ => Err(binary_type_error(">", self, other))
         }
     }
 
-    pub fn greater_or_equal(&self, other: &Object) -> Object {
+    pub fn less(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Boolean(left >= right),
-            (Object::Float(left), Object::Float(right)) => Object::Boolean(left >= right),
-            (Object::Int(left), Object::Float(right)) => Object::Boolean(*left as f64 >= *right),
-            (Object::Float(left), Object::Int(right)) => Object::Boolean(*left >= *right as f64),
-            (Object::Str(left), Object::Str(right)) => Object::Boolean(left >= right),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Boolean(left < right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Boolean(left < right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Boolean((*left as f64) < *right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Boolean(*left < *right as f64)),
+            (Object::Str(left), Object::Str(right)) => Ok(Object::Boolean(left < right)),
+            _ => Err(binary_type_error("<", self, other))
         }
     }
 
-    pub fn less_or_equal(&self, other: &Object) -> Object {
+    pub fn greater_or_equal(&self, other: &Object) -> Result<Object, Diagnostic> {
         match (self, other) {
-            (Object::Int(left), Object::Int(right)) => Object::Boolean(left <= right),
-            (Object::Float(left), Object::Float(right)) => Object::Boolean(left <= right),
-            (Object::Int(left), Object::Float(right)) => Object::Boolean((*left as f64) <= *right),
-            (Object::Float(left), Object::Int(right)) => Object::Boolean(*left <= *right as f64),
-            (Object::Str(left), Object::Str(right)) => Object::Boolean(left <= right),
-            _ => Object::Null
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Boolean(left >= right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Boolean(left >= right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Boolean(*left as f64 >= *right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Boolean(*left >= *right as f64)),
+            (Object::Str(left), Object::Str(right)) => Ok(Object::Boolean(left >= right)),
+            _ => Err(binary_type_error(">=", self, other))
         }
     }
 
-    pub fn and(&self, other: &Object) -> Object {
-        match (self, other){
-            (Object::Boolean(left), Object::Boolean(right)) => Object::Boolean(*left && *right),
-            _ => Object::Null
+    pub fn less_or_equal(&self, other: &Object) -> Result<Object, Diagnostic> {
+        match (self, other) {
+            (Object::Int(left), Object::Int(right)) => Ok(Object::Boolean(left <= right)),
+            (Object::Float(left), Object::Float(right)) => Ok(Object::Boolean(left <= right)),
+            (Object::Int(left), Object::Float(right)) => Ok(Object::Boolean((*left as f64) <= *right)),
+            (Object::Float(left), Object::Int(right)) => Ok(Object::Boolean(*left <= *right as f64)),
+            (Object::Str(left), Object::Str(right)) => Ok(Object::Boolean(left <= right)),
+            _ => Err(binary_type_error("<=", self, other))
         }
     }
 
-    pub fn or(&self, other: &Object) -> Object {
-        match (self, other) {
-            (Object::Boolean(left), Object::Boolean(right)) => Object::Boolean(*left || *right),
-            (Object::Null, _) => other.clone(),
-            (_, Object::Null) => self.clone(),
-            _ => Object::Null
-        } 
-    }
+    //'&&' and '||' are short-circuiting, so they live in the engine
 
     pub fn not(&self) -> Object {
+        Object::Boolean(!self.is_truthy())
+    }
+
+    //collections
+
+    pub fn get_item(&self, index: &Object) -> Result<Object, Diagnostic> {
         match self {
-            Object::Boolean(left) => Object::Boolean(!left),
-            _ => Object::Null
+            Object::Array(items) => {
+                let items = items.borrow();
+                let position = array_position(index, items.len())?;
+                Ok(items[position].clone())
+            },
+            Object::Map(map) => {
+                let key = map_key(index)?;
+                match map.borrow().get(&key) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(Diagnostic::runtime(format!("Key {:?} not found in map", key)))
+                }
+            },
+            _ => Err(Diagnostic::type_error(format!("Can't index '{}' value", self.type_name())))
         }
     }
 
-    pub fn unary_plus(&self) -> Object {
+    pub fn set_item(&self, index: &Object, value: Object) -> Result<(), Diagnostic> {
+        match self {
+            Object::Array(items) => {
+                let mut items = items.borrow_mut();
+                let position = array_position(index, items.len())?;
+                items[position] = value;
+                Ok(())
+            },
+            Object::Map(map) => {
+                map.borrow_mut().insert(map_key(index)?, value);
+                Ok(())
+            },
+            _ => Err(Diagnostic::type_error(format!(
+                "Can't assign by index to '{}' value", self.type_name()
+            )))
+        }
+    }
+
+    pub fn unary_plus(&self) -> Result<Object, Diagnostic> {
         let mut val = self.to_int();
         if let Object::Null = val {
             val = self.to_float();
         }
-        val
+        match val {
+            Object::Null => Err(unary_type_error("+", self)),
+            _ => Ok(val)
+        }
     }
 
-    pub fn unary_minus(&self) -> Object {
+    pub fn unary_minus(&self) -> Result<Object, Diagnostic> {
         match self {
-            Object::Float(left) => Object::Float(-*left),
-            Object::Int(left) => Object::Int(-*left),
-            _ => Object::Null
+            Object::Float(left) => Ok(Object::Float(-*left)),
+            Object::Int(left) => left.checked_neg()
+                .map(Object::Int)
+                .ok_or_else(|| Diagnostic::runtime(format!("Integer overflow in -({})", left))),
+            _ => Err(unary_type_error("-", self))
         }
     }
 
 }
 
-fn mul_str(mul: i32, string: &str) -> String {
+//turns possibly negative index into a position inside the array
+pub fn array_position(index: &Object, len: usize) -> Result<usize, Diagnostic> {
+    match index {
+        Object::Int(index) => {
+            let position = if *index < 0 { len as i64 + *index } else { *index };
+            if position < 0 || position >= len as i64 {
+                Err(Diagnostic::runtime(format!(
+                    "Index {} is out of bounds for array of length {}", index, len
+                )))
+            }
+            else {
+                Ok(position as usize)
+            }
+        },
+        _ => Err(Diagnostic::type_error(format!(
+            "Array index must be int, not '{}'", index.type_name()
+        )))
+    }
+}
+
+pub fn map_key(key: &Object) -> Result<String, Diagnostic> {
+    match key {
+        Object::Str(key) => Ok(key.clone()),
+        _ => Err(Diagnostic::type_error(format!(
+            "Map key must be string, not '{}'", key.type_name()
+        )))
+    }
+}
+
+fn binary_type_error(operator: &str, left: &Object, right: &Object) -> Diagnostic {
+    Diagnostic::type_error(format!(
+        "Operator '{}' can't be applied to '{}' and '{}'",
+        operator, left.type_name(), right.type_name()
+    ))
+}
+
+fn unary_type_error(operator: &str, value: &Object) -> Diagnostic {
+    Diagnostic::type_error(format!(
+        "Unary operator '{}' can't be applied to '{}'", operator, value.type_name()
+    ))
+}
+
+//ints are 64-bit, results which don't fit are errors, not wrapped values
+fn overflow_error(operator: &str, left: i64, right: i64) -> Diagnostic {
+    Diagnostic::runtime(format!("Integer overflow in {} {} {}", left, operator, right))
+}
+
+fn shift_amount(shift: i64) -> Result<u32, Diagnostic> {
+    match u32::try_from(shift) {
+        Ok(shift) if shift < 64 => Ok(shift),
+        _ => Err(Diagnostic::runtime(format!("Shift by {} must be from 0 to 63", shift)))
+    }
+}
+
+//fraction is dropped, null if the float has no int value
+fn float_to_int(x: f64) -> Object {
+    //i64::MAX as f64 is 2^63, which is already too big
+    if x.is_finite() && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+        Object::Int(x as i64)
+    }
+    else {
+        Object::Null
+    }
+}
+
+fn mul_str(mul: i64, string: &str) -> String {
     let mut val = String::new();
     for _ in 0..mul {
         val.push_str(string)
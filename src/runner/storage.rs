@@ -1,5 +1,8 @@
 use super::object::Object;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
 
 struct Mem {
     vars: HashMap<String, Object>
@@ -14,10 +17,7 @@ impl Mem {
     }
 
     fn get(&self, name: &str) -> Option<Object> {
-        match self.vars.get(name) {
-            None => None,
-            Some(object) => Some(object.clone())
-        }
+        self.vars.get(name).cloned()
     }
 
     fn set(&mut self, name: String, value: Object) {
@@ -32,25 +32,64 @@ impl Mem {
 
 //core struct of lang's memory
 //stack of memory frames
+//frames are shared, so functions can capture the stack they were declared in
+//(the first frame is the global one)
+#[derive(Clone)]
 pub struct MemStack {
-    stack: Vec<Mem>
+    stack: Vec<Rc<RefCell<Mem>>>
+}
+
+impl Default for MemStack {
+    fn default() -> MemStack {
+        MemStack::new()
+    }
 }
 
 impl MemStack {
 
     pub fn new() -> MemStack {
         MemStack {
-            stack: vec![Mem::new()]
+            stack: vec![Rc::new(RefCell::new(Mem::new()))]
         }
     }
 
-    pub fn scope(&mut self) -> &mut Mem {
+    fn scope(&self) -> &Rc<RefCell<Mem>> {
         let stack_size = self.stack.len();
-        &mut self.stack[stack_size - 1]
+        &self.stack[stack_size - 1]
+    }
+
+    //scopes a function declared here captures; the global scope is left out
+    //and the one its binding goes to is held weakly, a function stored there
+    //would otherwise keep that scope alive through itself
+    pub fn capture(&self, name: &str) -> Closure {
+        let binding = self.binding_scope(name);
+        let scopes = self.stack.iter().enumerate().skip(1)
+            .map(|(i, mem)| if i == binding {
+                Captured::Weak(Rc::downgrade(mem))
+            }
+            else {
+                Captured::Strong(Rc::clone(mem))
+            })
+            .collect();
+        Closure { scopes }
+    }
+
+    //stack a function body runs in: the global scope and the captured ones
+    pub fn with_closure(&self, closure: &Closure) -> MemStack {
+        let mut stack = vec![Rc::clone(&self.stack[0])];
+        stack.extend(closure.upgrade());
+        MemStack { stack }
+    }
+
+    //index of the scope set_or_rewrite_var would store name in
+    fn binding_scope(&self, name: &str) -> usize {
+        self.stack.iter()
+            .rposition(|mem| mem.borrow().has(name))
+            .unwrap_or(self.stack.len() - 1)
     }
 
     pub fn new_scope(&mut self) {
-        self.stack.push(Mem::new());
+        self.stack.push(Rc::new(RefCell::new(Mem::new())));
     }
 
     pub fn leave_scope(&mut self) {
@@ -58,27 +97,82 @@ impl MemStack {
     }
 
     pub fn set_var(&mut self, name: String, value: Object) {
-        let current_scope = self.scope();
-        current_scope.set(name, value)
+        self.scope().borrow_mut().set(name, value)
     }
 
-    pub fn get_var(&mut self, name: String) -> Object {
+    //arrays and maps are cloned as references, so the caller shares them;
+    //functions leave their binding holding all captured scopes
+    pub fn get_var(&self, name: &str) -> Object {
         for mem in self.stack.iter().rev() {
-            if let Some(object) = mem.get(&name) {
-                return object;
+            if let Some(object) = mem.borrow().get(name) {
+                return match object {
+                    Object::Function(function, closure) => Object::Function(function, closure.strong()),
+                    object => object
+                };
             }
         }
         Object::Null
     }
 
+    //variables of every scope, outermost first, sorted by name
+    pub fn scopes(&self) -> Vec<Vec<(String, Object)>> {
+        self.stack.iter().map(|mem| {
+            let mut vars: Vec<(String, Object)> = mem.borrow().vars
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            vars.sort_by(|left, right| left.0.cmp(&right.0));
+            vars
+        }).collect()
+    }
+
     pub fn set_or_rewrite_var(&mut self, name: String, value: Object) {
-        for mem in self.stack.iter_mut().rev() {
-            if mem.has(&name) {
-                mem.set(name.clone(), value.clone());
+        for mem in self.stack.iter().rev() {
+            if mem.borrow().has(&name) {
+                mem.borrow_mut().set(name, value);
                 return;
             }
         }
         self.set_var(name, value);
     }
 
-}
\ No newline at end of file
+}
+
+#[derive(Clone)]
+enum Captured {
+    Strong(Rc<RefCell<Mem>>),
+    Weak(Weak<RefCell<Mem>>)
+}
+
+//scopes a function was declared in, outermost first
+#[derive(Clone)]
+pub struct Closure {
+    scopes: Vec<Captured>
+}
+
+impl Closure {
+    //a weak scope is gone only when its binding can't be read anymore,
+    //so there is nothing to lose by skipping it
+    fn upgrade(&self) -> Vec<Rc<RefCell<Mem>>> {
+        self.scopes.iter()
+            .filter_map(|scope| match scope {
+                Captured::Strong(mem) => Some(Rc::clone(mem)),
+                Captured::Weak(mem) => mem.upgrade()
+            })
+            .collect()
+    }
+
+    //copy of a function value which keeps all its scopes alive
+    pub fn strong(&self) -> Closure {
+        Closure {
+            scopes: self.upgrade().into_iter().map(Captured::Strong).collect()
+        }
+    }
+}
+
+//scopes are skipped: they usually contain the function itself
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure")
+    }
+}
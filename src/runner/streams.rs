@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+//in-memory output for tests and embedders:
+//clones share the same bytes, so one clone can be given to the engine
+//and the other one read afterwards
+//(for input any BufRead works, for example io::Cursor)
+#[derive(Debug, Clone, Default)]
+pub struct Buffer {
+    bytes: Rc<RefCell<Vec<u8>>>
+}
+
+impl Buffer {
+    pub fn new() -> Buffer {
+        Buffer::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.bytes.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.bytes.borrow_mut().clear();
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.bytes.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
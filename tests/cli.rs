@@ -0,0 +1,136 @@
+//tests of the command line: subcommands, their output and exit codes
+
+use std::process::Command;
+
+//stdout, stderr and exit code of the binary
+fn language(args: &[&str]) -> (String, String, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_language"))
+        .args(args)
+        .output()
+        .expect("Can't start the binary!");
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+        output.status.code().expect("Killed by a signal!")
+    )
+}
+
+const COUNTING_TESTS: &str = "
+count = 0;
+function test_a() { count = count + 1; assert_eq(count, 1); }
+function test_b() { count = count + 1; assert_eq(count, 1); }
+";
+
+#[test]
+fn test_command_isolates_tests() {
+    let (stdout, _, code) = language(&["test", "-e", COUNTING_TESTS]);
+    assert_eq!(stdout, "running 2 tests\n\
+        test test_a ... ok\n\
+        test test_b ... ok\n\
+        \n\
+        test result: ok. 2 passed; 0 failed\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn test_command_reports_failures() {
+    let source = "function test_good() {} function test_bad() { assert(false); }";
+    let (stdout, _, code) = language(&["test", "-e", source]);
+    assert!(stdout.contains("test test_good ... ok\n"), "{}", stdout);
+    assert!(stdout.contains("test test_bad ... FAILED\n"), "{}", stdout);
+    assert!(stdout.contains("---- test_bad ----\n"), "{}", stdout);
+    assert!(stdout.ends_with("test result: FAILED. 1 passed; 1 failed\n"), "{}", stdout);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn test_command_runs_top_level_once() {
+    let source = "echo \"setup\"; function test_a() { echo \"in test\"; } exit(0);";
+    let (stdout, _, code) = language(&["test", "-e", source]);
+    assert_eq!(stdout, "setup\n\
+        running 1 test\n\
+        in test\n\
+        test test_a ... ok\n\
+        \n\
+        test result: ok. 1 passed; 0 failed\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn inline_code_is_run() {
+    let (stdout, _, code) = language(&["-e", "echo 1 + 2;"]);
+    assert_eq!(stdout, "3\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn file_is_run_by_default() {
+    let (stdout, _, code) = language(&["tests/scripts/arithmetic.lang"]);
+    assert!(stdout.starts_with("7\n9\n"), "{}", stdout);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn arguments_after_double_dash_go_to_script() {
+    let (stdout, _, code) = language(&["-e", "echo args();", "--", "a", "-b", "--"]);
+    assert_eq!(stdout, "[\"a\", \"-b\", \"--\"]\n");
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn check_only_parses() {
+    let (stdout, stderr, code) = language(&["check", "-e", "echo 1 + null;"]);
+    assert_eq!((&stdout[..], &stderr[..], code), ("", "", 0));
+}
+
+#[test]
+fn help_prints_usage() {
+    let (stdout, _, code) = language(&["--help"]);
+    assert!(stdout.starts_with("usage: language"), "{}", stdout);
+    assert_eq!(code, 0);
+}
+
+#[test]
+fn runtime_error_exits_with_1() {
+    let (_, stderr, code) = language(&["-e", "echo 1 + null;"]);
+    assert!(stderr.starts_with("error[type]"), "{}", stderr);
+    assert_eq!(code, 1);
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    let cases: [&[&str]; 4] = [
+        &["--bogus"],
+        &["check"],
+        &["-e"],
+        &["-e", "echo 1;", "file.lang"]
+    ];
+    for args in &cases {
+        let (_, stderr, code) = language(args);
+        assert!(stderr.contains("usage:"), "{:?}: {}", args, stderr);
+        assert_eq!(code, 2, "{:?}", args);
+    }
+}
+
+#[test]
+fn syntax_error_exits_with_3() {
+    for command in &["run", "check", "ast", "test"] {
+        let (_, stderr, code) = language(&[command, "-e", "x = ;"]);
+        assert!(stderr.starts_with("error[syntax]"), "{}: {}", command, stderr);
+        assert_eq!(code, 3, "{}", command);
+    }
+}
+
+#[test]
+fn missing_file_exits_with_4() {
+    let (_, stderr, code) = language(&["run", "no/such/file.lang"]);
+    assert!(stderr.contains("error[io]"), "{}", stderr);
+    assert_eq!(code, 4);
+}
+
+#[test]
+fn exit_status_is_passed_on() {
+    let (stdout, _, code) = language(&["-e", "echo \"before\"; exit(7); echo \"after\";"]);
+    assert_eq!(stdout, "before\n");
+    assert_eq!(code, 7);
+}
@@ -0,0 +1,148 @@
+//tests of the rust api which the golden scripts can't express
+
+use language::report::diagnostic::ErrorKind;
+use language::report::render::render;
+use language::{Buffer, Error, Interpreter, Object};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+//sets the flag when the closure holding it is dropped
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+#[test]
+fn registered_closure_is_dropped_with_interpreter() {
+    let dropped = Rc::new(Cell::new(false));
+    let flag = DropFlag(Rc::clone(&dropped));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Buffer::new());
+    interpreter.register("ping", move || {
+        let _ = &flag;
+        1
+    });
+    interpreter.run("function f() { return ping(); } echo f();").unwrap();
+    drop(interpreter);
+    assert!(dropped.get());
+}
+
+const NESTED_CALLS: &str = "function inner(x) {
+    return x + null;
+}
+function outer() {
+    return inner(1);
+}
+outer();
+";
+
+fn runtime_error(source: &str) -> Error {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Buffer::new());
+    interpreter.run(source).unwrap_err()
+}
+
+#[test]
+fn traceback_lists_calls_outermost_first() {
+    let error = runtime_error(NESTED_CALLS);
+    let calls: Vec<(&str, String)> = error.diagnostics()[0].traceback.iter()
+        .map(|entry| (&entry.function[..], entry.span.to_string()))
+        .collect();
+    assert_eq!(calls, vec![
+        ("<main>", "7:1".to_string()),
+        ("outer", "5:12".to_string())
+    ]);
+}
+
+#[test]
+fn traceback_is_rendered_before_error() {
+    let error = runtime_error(NESTED_CALLS);
+    let rendered = render(&error.diagnostics()[0], NESTED_CALLS, "main.lang");
+    let expected = "traceback (most recent call last):
+  main.lang:7:1, in <main>
+    outer();
+  main.lang:5:12, in outer
+    return inner(1);
+error[type]: Operator '+' can't be applied to 'int' and 'null'
+ --> main.lang:2:12
+";
+    assert!(rendered.starts_with(expected), "rendered:\n{}", rendered);
+}
+
+#[test]
+fn caught_callback_error_keeps_outer_frames() {
+    let source = "function bad() { return 1 + null; }
+function boom() { return null + 1; }
+function outer() {
+    try_call(bad);
+    return boom();
+}
+function top() {
+    return outer();
+}
+top();
+";
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Buffer::new());
+    //swallows the error of the callback
+    interpreter.register_native("try_call", |engine, args| {
+        let _ = engine.call(&args[0], Vec::new());
+        Ok(Object::Null)
+    });
+    let error = interpreter.run(source).unwrap_err();
+    let calls: Vec<(&str, String)> = error.diagnostics()[0].traceback.iter()
+        .map(|entry| (&entry.function[..], entry.span.to_string()))
+        .collect();
+    assert_eq!(calls, vec![
+        ("<main>", "10:1".to_string()),
+        ("top", "8:12".to_string()),
+        ("outer", "5:12".to_string())
+    ]);
+}
+
+fn strong_count(interpreter: &Interpreter, name: &str) -> usize {
+    match interpreter.get::<Object>(name).unwrap() {
+        Object::Array(items) => Rc::strong_count(&items) - 1,
+        other => panic!("{} is not an array: {}", name, other)
+    }
+}
+
+#[test]
+fn nested_function_does_not_keep_its_scope_alive() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(Buffer::new());
+    interpreter.run("xs = [1, 2, 3];
+function factory() {
+    local = xs;
+    function inner() { return local; }
+    return inner;
+}
+").unwrap();
+    let count = strong_count(&interpreter, "xs");
+    interpreter.run("factory(); factory(); factory();").unwrap();
+    assert_eq!(strong_count(&interpreter, "xs"), count);
+
+    //a returned function keeps the scope while it is alive
+    interpreter.run("kept = factory();").unwrap();
+    assert_eq!(strong_count(&interpreter, "xs"), count + 1);
+    interpreter.run("kept = null;").unwrap();
+    assert_eq!(strong_count(&interpreter, "xs"), count);
+}
+
+#[test]
+fn wrong_argument_types_are_type_errors() {
+    let sources = [
+        "len(1);", "push(1, 2);", "pop(\"a\");", "insert({}, 0, 1);", "remove(1, 0);",
+        "keys([]);", "values(1);", "has([], \"a\");", "delete(1, \"a\");",
+        "x = 1; echo x[0];", "x = 1; x[0] = 2;"
+    ];
+    for source in &sources {
+        assert_eq!(runtime_error(source).kind(), ErrorKind::Type, "{}", source);
+    }
+    assert_eq!(runtime_error("len(1);").diagnostics()[0].message,
+        "Builtin 'len' expects array, map or string, not 'int'");
+}
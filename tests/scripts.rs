@@ -0,0 +1,109 @@
+//golden-file tests: every tests/scripts/*.lang is run through the interpreter,
+//its output (and error, if any) is compared with '# expect:' comments
+//or, when there is one, with the sibling .out file
+
+use language::{Buffer, Interpreter};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+const EXPECT: &str = "# expect:";
+
+//test threads have a small stack, scripts get one as big as the main thread's,
+//which is what the call depth limit is made for
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+//stdout of the script, then its error in the one-line form
+fn run_script(source: &str) -> String {
+    let source = source.to_string();
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let output = Buffer::new();
+            let mut interpreter = Interpreter::new();
+            interpreter.set_output(output.clone());
+            interpreter.set_input(io::empty());
+            let result = interpreter.run(&source);
+            let mut actual = output.contents();
+            if let Err(error) = result {
+                actual.push_str(&format!("{}\n", error));
+            }
+            actual
+        })
+        .expect("Can't start script thread")
+        .join()
+        .expect("Script thread panicked")
+}
+
+fn expected_output(path: &Path, source: &str) -> Option<String> {
+    if let Ok(expected) = fs::read_to_string(path.with_extension("out")) {
+        return Some(expected);
+    }
+    let lines: Vec<&str> = source.lines()
+        .filter_map(|line| line.find(EXPECT).map(|i| &line[i + EXPECT.len()..]))
+        .map(|rest| rest.strip_prefix(' ').unwrap_or(rest).trim_end())
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(lines.iter().map(|line| format!("{}\n", line)).collect())
+}
+
+//line by line, '-' is expected and '+' is actual
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(left), Some(right)) if left == right => out.push_str(&format!("  {}\n", left)),
+            (left, right) => {
+                if let Some(left) = left {
+                    out.push_str(&format!("- {}\n", left));
+                }
+                if let Some(right) = right {
+                    out.push_str(&format!("+ {}\n", right));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn scripts() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("scripts");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .expect("Can't read tests/scripts")
+        .map(|entry| entry.expect("Can't read tests/scripts").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lang"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn golden_scripts() {
+    let mut failures = Vec::new();
+    let paths = scripts();
+    for path in &paths {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let source = fs::read_to_string(path).expect("Can't read script");
+        let expected = match expected_output(path, &source) {
+            Some(expected) => expected,
+            None => {
+                failures.push(format!("{}: no '{}' comments and no .out file", name, EXPECT));
+                continue;
+            }
+        };
+        let actual = run_script(&source);
+        if actual != expected {
+            failures.push(format!("{}:\n{}", name, diff(&expected, &actual)));
+        }
+    }
+    assert!(!paths.is_empty(), "no scripts in tests/scripts");
+    if !failures.is_empty() {
+        panic!("{} of {} scripts failed\n\n{}", failures.len(), paths.len(), failures.join("\n"));
+    }
+}